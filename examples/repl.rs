@@ -1,3 +1,4 @@
+use codespan_reporting::diagnostic::Diagnostic;
 use codespan_reporting::files::SimpleFile;
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 use codespan_reporting::term::{self, Config};
@@ -23,12 +24,11 @@ fn main() -> io::Result<()> {
 
         let parse = expr_parser::Parser::new(&input).parse();
 
-        display_diagnostics(&input, &parse)?;
+        display_diagnostics(&input, parse.diagnostics(()))?;
 
-        if let Some(result) = parse.eval() {
-            writeln!(stdout, "{}", result)?;
-        } else {
-            writeln!(stderr, "failed to evaluate")?;
+        match parse.eval() {
+            Ok(result) => writeln!(stdout, "{}", result)?,
+            Err(error) => display_diagnostics(&input, std::iter::once(error.as_diagnostic(())))?,
         }
 
         writeln!(stderr, "\n{}", parse.format())?;
@@ -37,11 +37,12 @@ fn main() -> io::Result<()> {
     }
 }
 
-fn display_diagnostics(input: &str, parse: &expr_parser::Parse) -> io::Result<()> {
+fn display_diagnostics(
+    input: &str,
+    diagnostics: impl Iterator<Item = Diagnostic<()>>,
+) -> io::Result<()> {
     let file = SimpleFile::new("REPL input", &input);
 
-    let diagnostics = parse.diagnostics(());
-
     let writer = StandardStream::stderr(ColorChoice::Auto);
     let mut writer = writer.lock();
 
@@ -1,18 +1,18 @@
 mod eval;
+pub(crate) use eval::Context;
 
-use crate::lexer::SyntaxKind;
-use crate::{Op, SyntaxElement, SyntaxNode, SyntaxToken};
+use crate::literal::{self, Literal};
+use crate::{Op, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
 use rowan::SmolStr;
+use text_size::TextRange;
 
 macro_rules! ast_node {
     ($name:ident, $($syntax_kind:expr),+) => {
-        #[allow(unused)]
-        #[derive(Debug)]
-        pub(crate) struct $name(SyntaxNode);
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $name(SyntaxNode);
 
         impl $name {
-            #[allow(unused)]
-            pub(crate) fn cast(node: SyntaxNode) -> Option<Self> {
+            pub fn cast(node: SyntaxNode) -> Option<Self> {
                 if $(node.kind() == $syntax_kind)||+ {
                     Some(Self(node))
                 } else {
@@ -20,6 +20,14 @@ macro_rules! ast_node {
                 }
             }
 
+            pub fn syntax(&self) -> &SyntaxNode {
+                &self.0
+            }
+
+            pub fn text_range(&self) -> TextRange {
+                self.0.text_range()
+            }
+
             #[allow(unused)]
             fn text(&self) -> &SmolStr {
                 match &self.0.green().children().next() {
@@ -33,22 +41,27 @@ macro_rules! ast_node {
 
 macro_rules! ast_token {
     ($name:ident, $($syntax_kind:expr),+) => {
-        #[allow(unused)]
-        #[derive(Debug)]
-        pub(crate) struct $name(SyntaxToken);
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $name(SyntaxToken);
 
         impl $name {
-            #[allow(unused)]
-            pub(crate) fn cast(node: SyntaxToken) -> Option<Self> {
-                if $(node.kind() == $syntax_kind)||+ {
-                    Some(Self(node))
+            pub fn cast(token: SyntaxToken) -> Option<Self> {
+                if $(token.kind() == $syntax_kind)||+ {
+                    Some(Self(token))
                 } else {
                     None
                 }
             }
 
-            #[allow(unused)]
-            fn text(&self) -> &SmolStr {
+            pub fn syntax(&self) -> &SyntaxToken {
+                &self.0
+            }
+
+            pub fn text_range(&self) -> TextRange {
+                self.0.text_range()
+            }
+
+            pub fn text(&self) -> &SmolStr {
                 self.0.text()
             }
         }
@@ -56,23 +69,162 @@ macro_rules! ast_token {
 }
 
 ast_node!(Root, SyntaxKind::Root);
+ast_node!(NameRef, SyntaxKind::NameRef);
 ast_node!(Operation, SyntaxKind::Operation);
+ast_node!(ParenExpr, SyntaxKind::ParenExpr);
+ast_node!(PrefixExpr, SyntaxKind::PrefixExpr);
+ast_node!(CallExpr, SyntaxKind::CallExpr);
+ast_node!(ArgList, SyntaxKind::ArgList);
+ast_node!(ConditionalExpr, SyntaxKind::ConditionalExpr);
+ast_node!(LetStmt, SyntaxKind::LetStmt);
+ast_node!(ExprStmt, SyntaxKind::ExprStmt);
+ast_node!(Lambda, SyntaxKind::Lambda);
+ast_node!(ParamList, SyntaxKind::ParamList);
+ast_node!(ListExpr, SyntaxKind::ListExpr);
+ast_node!(IndexExpr, SyntaxKind::IndexExpr);
+ast_node!(Missing, SyntaxKind::Missing);
 
 ast_token!(Number, SyntaxKind::Number);
+ast_token!(Str, SyntaxKind::String);
+ast_token!(Name, SyntaxKind::Ident);
+ast_token!(Boolean, SyntaxKind::TrueKw, SyntaxKind::FalseKw);
 ast_token!(
     Operator,
     SyntaxKind::Plus,
     SyntaxKind::Star,
     SyntaxKind::Slash,
-    SyntaxKind::Minus
+    SyntaxKind::Minus,
+    SyntaxKind::DivKw,
+    SyntaxKind::Percent,
+    SyntaxKind::Caret,
+    SyntaxKind::EqEq,
+    SyntaxKind::BangEq,
+    SyntaxKind::Less,
+    SyntaxKind::LessEq,
+    SyntaxKind::Greater,
+    SyntaxKind::GreaterEq,
+    SyntaxKind::AmpAmp,
+    SyntaxKind::PipePipe,
+    SyntaxKind::Bang
 );
 
+impl Root {
+    // The statements that come before the final expression.
+    pub fn stmts(&self) -> impl Iterator<Item = Stmt> {
+        self.0.children().filter_map(Stmt::cast)
+    }
+
+    // The final expression, which isn’t terminated by a semicolon and gives the program its value.
+    pub fn expr(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).next()
+    }
+}
+
+impl LetStmt {
+    pub fn name(&self) -> Option<Name> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|element| element.into_token())
+            .find_map(Name::cast)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).next()
+    }
+}
+
+impl ExprStmt {
+    pub fn expr(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).next()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Stmt {
+    Let(LetStmt),
+    Expr(ExprStmt),
+}
+
+impl Stmt {
+    pub fn text_range(&self) -> TextRange {
+        match self {
+            Self::Let(l) => l.0.text_range(),
+            Self::Expr(e) => e.0.text_range(),
+        }
+    }
+
+    pub fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Let(l) => &l.0,
+            Self::Expr(e) => &e.0,
+        }
+    }
+
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        LetStmt::cast(node.clone())
+            .map(Self::Let)
+            .or_else(|| ExprStmt::cast(node).map(Self::Expr))
+    }
+}
+
+impl Number {
+    pub fn value(&self) -> Option<Literal> {
+        literal::parse(self.text()).ok()
+    }
+}
+
+impl Str {
+    // Returns None if the literal is malformed.
+    pub fn value(&self) -> Option<String> {
+        literal::parse_string(self.text()).ok()
+    }
+}
+
+impl Boolean {
+    pub fn value(&self) -> bool {
+        self.0.kind() == SyntaxKind::TrueKw
+    }
+}
+
+impl NameRef {
+    pub fn name(&self) -> &str {
+        self.text()
+    }
+}
+
 impl Operation {
-    fn lhs(&self) -> Option<Expr> {
+    pub fn lhs(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).next()
+    }
+
+    pub fn op(&self) -> Option<Operator> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|element| element.into_token())
+            .filter_map(Operator::cast)
+            .next()
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).nth(1)
+    }
+
+    // Whether the right-hand side is only evaluated depending on the value of the left-hand
+    // side, as with `&&` and `||`.
+    pub fn is_short_circuiting(&self) -> bool {
+        self.op()
+            .is_some_and(|op| matches!(op.into(), Op::And | Op::Or))
+    }
+}
+
+impl ParenExpr {
+    pub fn expr(&self) -> Option<Expr> {
         self.0.children_with_tokens().filter_map(Expr::cast).next()
     }
+}
 
-    fn op(&self) -> Option<Operator> {
+impl PrefixExpr {
+    pub fn op(&self) -> Option<Operator> {
         self.0
             .children_with_tokens()
             .filter_map(|element| element.into_token())
@@ -80,7 +232,88 @@ impl Operation {
             .next()
     }
 
-    fn rhs(&self) -> Option<Expr> {
+    pub fn operand(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).next()
+    }
+}
+
+impl CallExpr {
+    pub fn callee(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).next()
+    }
+
+    pub fn arg_list(&self) -> Option<ArgList> {
+        self.0.children().find_map(ArgList::cast)
+    }
+}
+
+impl ArgList {
+    pub fn args(&self) -> impl Iterator<Item = Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast)
+    }
+}
+
+impl ConditionalExpr {
+    pub fn condition(&self) -> Option<Expr> {
+        self.part_after(SyntaxKind::IfKw)
+    }
+
+    pub fn then_branch(&self) -> Option<Expr> {
+        self.part_after(SyntaxKind::ThenKw)
+    }
+
+    pub fn else_branch(&self) -> Option<Expr> {
+        self.part_after(SyntaxKind::ElseKw)
+    }
+
+    // Each part is the expression between its keyword and the next one, so that a missing part
+    // doesn’t shift the others along.
+    fn part_after(&self, keyword: SyntaxKind) -> Option<Expr> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|element| element.kind() != keyword)
+            .skip(1)
+            .take_while(|element| {
+                !matches!(
+                    element.kind(),
+                    SyntaxKind::IfKw | SyntaxKind::ThenKw | SyntaxKind::ElseKw
+                )
+            })
+            .find_map(Expr::cast)
+    }
+}
+
+impl Lambda {
+    pub fn param_list(&self) -> Option<ParamList> {
+        self.0.children().find_map(ParamList::cast)
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).next()
+    }
+}
+
+impl ParamList {
+    pub fn params(&self) -> impl Iterator<Item = Name> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|element| element.into_token())
+            .filter_map(Name::cast)
+    }
+}
+
+impl ListExpr {
+    pub fn items(&self) -> impl Iterator<Item = Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast)
+    }
+}
+
+impl IndexExpr {
+    pub fn base(&self) -> Option<Expr> {
+        self.0.children_with_tokens().filter_map(Expr::cast).next()
+    }
+
+    pub fn index(&self) -> Option<Expr> {
         self.0.children_with_tokens().filter_map(Expr::cast).nth(1)
     }
 }
@@ -92,24 +325,106 @@ impl From<Operator> for Op {
             "-" => Self::Sub,
             "*" => Self::Mul,
             "/" => Self::Div,
+            "div" => Self::DivEuclid,
+            "%" => Self::RemEuclid,
+            "^" => Self::Pow,
+            "==" => Self::Eq,
+            "!=" => Self::Ne,
+            "<" => Self::Lt,
+            "<=" => Self::Le,
+            ">" => Self::Gt,
+            ">=" => Self::Ge,
+            "&&" => Self::And,
+            "||" => Self::Or,
+            "!" => Self::Not,
             _ => unreachable!(),
         }
     }
 }
 
-#[derive(Debug)]
-enum Expr {
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
     Number(Number),
+    Str(Str),
+    Boolean(Boolean),
+    NameRef(NameRef),
     Operation(Operation),
+    ParenExpr(ParenExpr),
+    PrefixExpr(PrefixExpr),
+    CallExpr(CallExpr),
+    ConditionalExpr(ConditionalExpr),
+    Lambda(Lambda),
+    ListExpr(ListExpr),
+    IndexExpr(IndexExpr),
+    // Keeping holes left by syntax errors as expressions means that the expressions after them
+    // are still found in the right place.
+    Missing(Missing),
 }
 
 impl Expr {
-    fn cast(element: SyntaxElement) -> Option<Self> {
+    pub fn text_range(&self) -> TextRange {
+        match self {
+            Self::Number(n) => n.0.text_range(),
+            Self::Str(s) => s.0.text_range(),
+            Self::Boolean(b) => b.0.text_range(),
+            Self::NameRef(n) => n.0.text_range(),
+            Self::Operation(o) => o.0.text_range(),
+            Self::ParenExpr(p) => p.0.text_range(),
+            Self::PrefixExpr(p) => p.0.text_range(),
+            Self::CallExpr(c) => c.0.text_range(),
+            Self::ConditionalExpr(c) => c.0.text_range(),
+            Self::Lambda(l) => l.0.text_range(),
+            Self::ListExpr(l) => l.0.text_range(),
+            Self::IndexExpr(i) => i.0.text_range(),
+            Self::Missing(m) => m.0.text_range(),
+        }
+    }
+
+    pub fn syntax(&self) -> SyntaxElement {
+        match self {
+            Self::Number(n) => n.0.clone().into(),
+            Self::Str(s) => s.0.clone().into(),
+            Self::Boolean(b) => b.0.clone().into(),
+            Self::NameRef(n) => n.0.clone().into(),
+            Self::Operation(o) => o.0.clone().into(),
+            Self::ParenExpr(p) => p.0.clone().into(),
+            Self::PrefixExpr(p) => p.0.clone().into(),
+            Self::CallExpr(c) => c.0.clone().into(),
+            Self::ConditionalExpr(c) => c.0.clone().into(),
+            Self::Lambda(l) => l.0.clone().into(),
+            Self::ListExpr(l) => l.0.clone().into(),
+            Self::IndexExpr(i) => i.0.clone().into(),
+            Self::Missing(m) => m.0.clone().into(),
+        }
+    }
+
+    pub fn cast(element: SyntaxElement) -> Option<Self> {
         element
             .clone()
             .into_token()
             .and_then(Number::cast)
             .map(Self::Number)
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_token()
+                    .and_then(Str::cast)
+                    .map(Self::Str)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_token()
+                    .and_then(Boolean::cast)
+                    .map(Self::Boolean)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(NameRef::cast)
+                    .map(Self::NameRef)
+            })
             .or_else(|| {
                 element
                     .clone()
@@ -117,5 +432,310 @@ impl Expr {
                     .and_then(Operation::cast)
                     .map(Self::Operation)
             })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(ParenExpr::cast)
+                    .map(Self::ParenExpr)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(PrefixExpr::cast)
+                    .map(Self::PrefixExpr)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(CallExpr::cast)
+                    .map(Self::CallExpr)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(ConditionalExpr::cast)
+                    .map(Self::ConditionalExpr)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(Lambda::cast)
+                    .map(Self::Lambda)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(ListExpr::cast)
+                    .map(Self::ListExpr)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(IndexExpr::cast)
+                    .map(Self::IndexExpr)
+            })
+            .or_else(|| {
+                element
+                    .clone()
+                    .into_node()
+                    .and_then(Missing::cast)
+                    .map(Self::Missing)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+    use pretty_assertions::assert_eq;
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+    }
+
+    #[test]
+    fn operation_exposes_operands_and_operator() {
+        let parse = Parser::new(" 12 * x").parse();
+
+        let operation = match parse.root().expr() {
+            Some(Expr::Operation(operation)) => operation,
+            expr => panic!("expected an operation, found {:?}", expr),
+        };
+
+        assert_eq!(operation.text_range(), range(1, 7));
+
+        match operation.lhs() {
+            Some(Expr::Number(number)) => {
+                assert_eq!(number.text_range(), range(1, 3));
+                assert_eq!(
+                    number.value(),
+                    Some(Literal {
+                        significand: 12,
+                        exponent: 0,
+                    }),
+                );
+            }
+            lhs => panic!("expected a number, found {:?}", lhs),
+        }
+
+        assert_eq!(Op::from(operation.op().unwrap()), Op::Mul);
+
+        match operation.rhs() {
+            Some(Expr::NameRef(name_ref)) => assert_eq!(name_ref.name(), "x"),
+            rhs => panic!("expected a name, found {:?}", rhs),
+        }
+    }
+
+    #[test]
+    fn call_exposes_callee_and_arguments() {
+        let parse = Parser::new("max(1, 2)").parse();
+
+        let call = match parse.root().expr() {
+            Some(Expr::CallExpr(call)) => call,
+            expr => panic!("expected a call, found {:?}", expr),
+        };
+
+        assert!(matches!(call.callee(), Some(Expr::NameRef(_))));
+
+        let args: Vec<_> = call
+            .arg_list()
+            .unwrap()
+            .args()
+            .map(|arg| arg.text_range())
+            .collect();
+
+        assert_eq!(args, [range(4, 5), range(7, 8)]);
+    }
+
+    #[test]
+    fn expr_syntax_round_trips_through_cast() {
+        let parse = Parser::new("-(1)").parse();
+        let expr = parse.root().expr().unwrap();
+
+        assert_eq!(expr.syntax().kind(), SyntaxKind::PrefixExpr);
+        assert_eq!(Expr::cast(expr.syntax()), Some(expr));
+    }
+
+    #[test]
+    fn operation_exposes_whether_it_short_circuits() {
+        let is_short_circuiting = |input| match Parser::new(input).parse().root().expr() {
+            Some(Expr::Operation(operation)) => operation.is_short_circuiting(),
+            expr => panic!("expected an operation, found {:?}", expr),
+        };
+
+        assert!(is_short_circuiting("a && b"));
+        assert!(is_short_circuiting("a || b"));
+        assert!(!is_short_circuiting("a == b"));
+        assert!(!is_short_circuiting("a + b"));
+    }
+
+    #[test]
+    fn string_exposes_its_decoded_value() {
+        match Parser::new(r#""a\tb""#).parse().root().expr() {
+            Some(Expr::Str(string)) => assert_eq!(string.value().as_deref(), Some("a\tb")),
+            expr => panic!("expected a string, found {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn boolean_exposes_its_value() {
+        match Parser::new("false").parse().root().expr() {
+            Some(Expr::Boolean(boolean)) => assert!(!boolean.value()),
+            expr => panic!("expected a boolean, found {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn conditional_exposes_its_parts() {
+        let parse = Parser::new("if x then 1 else y").parse();
+
+        let conditional = match parse.root().expr() {
+            Some(Expr::ConditionalExpr(conditional)) => conditional,
+            expr => panic!("expected a conditional, found {:?}", expr),
+        };
+
+        assert_eq!(
+            conditional.condition().map(|expr| expr.text_range()),
+            Some(range(3, 4)),
+        );
+        assert_eq!(
+            conditional.then_branch().map(|expr| expr.text_range()),
+            Some(range(10, 11)),
+        );
+        assert_eq!(
+            conditional.else_branch().map(|expr| expr.text_range()),
+            Some(range(17, 18)),
+        );
+    }
+
+    #[test]
+    fn missing_conditional_parts_do_not_shift_the_others() {
+        let parse = Parser::new("if x else 2").parse();
+
+        let conditional = match parse.root().expr() {
+            Some(Expr::ConditionalExpr(conditional)) => conditional,
+            expr => panic!("expected a conditional, found {:?}", expr),
+        };
+
+        assert!(conditional.condition().is_some());
+        assert_eq!(conditional.then_branch(), None);
+        assert_eq!(
+            conditional.else_branch().map(|expr| expr.text_range()),
+            Some(range(10, 11)),
+        );
+    }
+
+    #[test]
+    fn root_exposes_statements_and_final_expression() {
+        let parse = Parser::new("let x = 1; x + 1; x").parse();
+        let root = parse.root();
+
+        let stmts: Vec<_> = root.stmts().collect();
+        assert_eq!(stmts.len(), 2);
+
+        match &stmts[0] {
+            Stmt::Let(stmt) => {
+                assert_eq!(stmt.name().unwrap().text(), "x");
+                assert_eq!(
+                    stmt.value().map(|expr| expr.text_range()),
+                    Some(range(8, 9))
+                );
+            }
+            stmt => panic!("expected a let statement, found {:?}", stmt),
+        }
+
+        match &stmts[1] {
+            Stmt::Expr(stmt) => {
+                assert_eq!(stmt.text_range(), range(11, 17));
+                assert!(matches!(stmt.expr(), Some(Expr::Operation(_))));
+            }
+            stmt => panic!("expected an expression statement, found {:?}", stmt),
+        }
+
+        assert_eq!(
+            root.expr().map(|expr| expr.text_range()),
+            Some(range(18, 19))
+        );
+    }
+
+    #[test]
+    fn lambda_exposes_parameters_and_body() {
+        let parse = Parser::new("fn(a, b) a + b").parse();
+
+        let lambda = match parse.root().expr() {
+            Some(Expr::Lambda(lambda)) => lambda,
+            expr => panic!("expected a lambda, found {:?}", expr),
+        };
+
+        let params: Vec<_> = lambda
+            .param_list()
+            .unwrap()
+            .params()
+            .map(|param| param.text().to_string())
+            .collect();
+
+        assert_eq!(params, ["a", "b"]);
+        assert_eq!(
+            lambda.body().map(|expr| expr.text_range()),
+            Some(range(9, 14)),
+        );
+    }
+
+    #[test]
+    fn list_exposes_its_items() {
+        let parse = Parser::new("[1, x + 2]").parse();
+
+        let list = match parse.root().expr() {
+            Some(Expr::ListExpr(list)) => list,
+            expr => panic!("expected a list, found {:?}", expr),
+        };
+
+        let items: Vec<_> = list.items().map(|item| item.text_range()).collect();
+        assert_eq!(items, [range(1, 2), range(4, 9)]);
+    }
+
+    #[test]
+    fn index_exposes_base_and_index() {
+        let parse = Parser::new("xs[i + 1]").parse();
+
+        let index = match parse.root().expr() {
+            Some(Expr::IndexExpr(index)) => index,
+            expr => panic!("expected an index, found {:?}", expr),
+        };
+
+        assert!(matches!(index.base(), Some(Expr::NameRef(_))));
+        assert_eq!(
+            index.index().map(|expr| expr.text_range()),
+            Some(range(3, 8)),
+        );
+    }
+
+    #[test]
+    fn missing_operand_does_not_shift_the_other_one() {
+        let parse = Parser::new("1 + * 2").parse();
+
+        let rhs = match parse.root().expr() {
+            Some(Expr::Operation(operation)) => operation.rhs(),
+            expr => panic!("expected an operation, found {:?}", expr),
+        };
+
+        let operation = match rhs {
+            Some(Expr::Operation(operation)) => operation,
+            rhs => panic!("expected an operation, found {:?}", rhs),
+        };
+
+        assert!(matches!(operation.lhs(), Some(Expr::Missing(_))));
+        assert_eq!(
+            operation.rhs().map(|expr| expr.text_range()),
+            Some(range(6, 7)),
+        );
     }
 }
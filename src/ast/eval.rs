@@ -1,41 +1,1837 @@
-use super::{Expr, Number, Operation, Root};
-use crate::Op;
+use super::{
+    ArgList, CallExpr, ConditionalExpr, Expr, IndexExpr, Lambda, ListExpr, NameRef, Number,
+    Operation, ParenExpr, PrefixExpr, Root, Stmt, Str,
+};
+use crate::env::{Environment, Locals};
+use crate::errors::{EvalError, EvalErrorKind};
+use crate::functions::{self, Arity, CallError, Function, FunctionRegistry};
+use crate::numeric::Numeric;
+use crate::value::{Closure, Value, ValueType};
+use crate::{Op, SyntaxNode};
+use std::cell::Cell;
+use std::convert::TryFrom;
+use text_size::TextRange;
+
+// How deeply expressions can be nested while evaluating, counting those in the bodies of the
+// closures being called. Every level takes stack, so the parser’s limit on nesting isn’t enough on
+// its own: each nested call adds the nesting of its closure’s body on top of the caller’s. This is
+// sized to fit in the 2 MiB stacks that threads get by default, even in debug builds.
+const MAX_DEPTH: usize = 512;
+
+// Everything evaluation needs besides the tree itself.
+pub(crate) struct Context<'a, T> {
+    env: &'a dyn Environment<T>,
+    functions: &'a FunctionRegistry<T>,
+    locals: Locals<T>,
+    // How many calls to closures we are currently nested inside.
+    calls: usize,
+    // How many expressions we are currently nested inside, including those in callers. Calls to
+    // closures count as a level of their own, since they take more stack than most expressions.
+    depth: Cell<usize>,
+}
+
+impl<'a, T> Context<'a, T> {
+    pub(crate) fn new(env: &'a dyn Environment<T>, functions: &'a FunctionRegistry<T>) -> Self {
+        Self {
+            env,
+            functions,
+            locals: Locals::new(),
+            calls: 0,
+            depth: Cell::new(0),
+        }
+    }
+
+    fn with_locals(&self, locals: Locals<T>) -> Self {
+        Self {
+            env: self.env,
+            functions: self.functions,
+            locals,
+            calls: self.calls,
+            depth: self.depth.clone(),
+        }
+    }
+
+    // Locals shadow variables from the environment.
+    fn lookup(&self, name: &str) -> Option<Value<T>>
+    where
+        T: Clone,
+    {
+        match self.locals.lookup(name) {
+            Some(value) => Some(value.clone()),
+            None => self.env.lookup(name),
+        }
+    }
+}
 
 impl Number {
-    fn eval(&self) -> u32 {
-        self.text().parse().unwrap()
+    fn eval<T: Numeric>(&self) -> Result<Value<T>, EvalError> {
+        // Malformed literals are reported by the parser, so the only way decoding can fail here
+        // is if the literal doesn’t fit.
+        self.value()
+            .and_then(T::from_literal)
+            .map(Value::Number)
+            .ok_or_else(|| EvalError::new(EvalErrorKind::LiteralOutOfRange, self.0.text_range()))
+    }
+}
+
+impl Str {
+    fn eval<T>(&self) -> Result<Value<T>, EvalError> {
+        // Malformed strings are reported by the parser.
+        self.value()
+            .map(Value::String)
+            .ok_or_else(|| EvalError::new(EvalErrorKind::IncompleteTree, self.0.text_range()))
+    }
+}
+
+impl NameRef {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        ctx.lookup(self.name()).ok_or_else(|| {
+            EvalError::new(
+                EvalErrorKind::UnboundVariable {
+                    name: self.name().to_string(),
+                },
+                self.0.text_range(),
+            )
+        })
     }
 }
 
 impl Operation {
-    fn eval(&self) -> Option<u32> {
-        let lhs = self.lhs()?.eval()?;
-        let rhs = self.rhs()?.eval()?;
+    // Left-associative chains such as 1 + 2 + 3 nest to the left, so rather than recursing into
+    // the left-hand side this walks down to the innermost operand and then applies each operator
+    // on the way back up. That way long chains don’t use stack in proportion to their length.
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        let mut operations = vec![self.clone()];
+        let mut innermost = self.lhs().ok_or_else(|| incomplete(&self.0))?;
+
+        while let Expr::Operation(operation) = innermost {
+            innermost = operation.lhs().ok_or_else(|| incomplete(&operation.0))?;
+            operations.push(operation);
+        }
+
+        let mut value = innermost.eval(ctx)?;
+
+        for operation in operations.iter().rev() {
+            value = operation.eval_with_lhs(value, ctx)?;
+        }
+
+        Ok(value)
+    }
+
+    fn eval_with_lhs<T: Numeric>(
+        &self,
+        lhs_value: Value<T>,
+        ctx: &Context<'_, T>,
+    ) -> Result<Value<T>, EvalError> {
+        let lhs = self.lhs().ok_or_else(|| incomplete(&self.0))?;
+        let op = self.op().ok_or_else(|| incomplete(&self.0))?;
+        let rhs = self.rhs().ok_or_else(|| incomplete(&self.0))?;
+
+        let op = op.into();
+
+        if let Op::And | Op::Or = op {
+            return self.eval_short_circuiting(op, &lhs, lhs_value, &rhs, ctx);
+        }
+
+        let rhs_value = rhs.eval(ctx)?;
+
+        let result = match op {
+            // Equality is defined between any two values of the same type.
+            Op::Eq => values_equal(&lhs_value, &rhs_value).map(Value::Bool),
+            Op::Ne => values_equal(&lhs_value, &rhs_value).map(|equal| Value::Bool(!equal)),
+            _ => match (&lhs_value, &rhs_value) {
+                (Value::Number(lhs_value), Value::Number(rhs_value)) => {
+                    eval_numeric_op(op, lhs_value, rhs_value)
+                }
+                (Value::String(_), _) | (_, Value::String(_)) if op == Op::Add => {
+                    concat(&lhs_value, &rhs_value)
+                }
+                (Value::Number(_), other) | (other, _) => Err(EvalErrorKind::TypeMismatch {
+                    expected: ValueType::Number,
+                    found: other.ty(),
+                }),
+            },
+        };
+
+        result.map_err(|kind| {
+            EvalError::new(kind, self.0.text_range())
+                .with_operand(lhs.text_range(), lhs_value)
+                .with_operand(rhs.text_range(), rhs_value)
+        })
+    }
+
+    // The right-hand side is only evaluated if the left-hand side doesn’t already decide the
+    // result, so `false && 1/0` is false rather than an error.
+    fn eval_short_circuiting<T: Numeric>(
+        &self,
+        op: Op,
+        lhs: &Expr,
+        lhs_value: Value<T>,
+        rhs: &Expr,
+        ctx: &Context<'_, T>,
+    ) -> Result<Value<T>, EvalError> {
+        let lhs_bool = match lhs_value {
+            Value::Bool(b) => b,
+            other => {
+                return Err(EvalError::new(
+                    EvalErrorKind::TypeMismatch {
+                        expected: ValueType::Bool,
+                        found: other.ty(),
+                    },
+                    self.0.text_range(),
+                )
+                .with_operand(lhs.text_range(), other))
+            }
+        };
+
+        if lhs_bool == (op == Op::Or) {
+            return Ok(Value::Bool(lhs_bool));
+        }
+
+        match rhs.eval(ctx)? {
+            Value::Bool(b) => Ok(Value::Bool(b)),
+            other => Err(EvalError::new(
+                EvalErrorKind::TypeMismatch {
+                    expected: ValueType::Bool,
+                    found: other.ty(),
+                },
+                self.0.text_range(),
+            )
+            .with_operand(lhs.text_range(), lhs_bool)
+            .with_operand(rhs.text_range(), other)),
+        }
+    }
+}
+
+fn values_equal<T: PartialEq>(lhs: &Value<T>, rhs: &Value<T>) -> Result<bool, EvalErrorKind> {
+    if lhs.ty() != rhs.ty() {
+        return Err(EvalErrorKind::TypeMismatch {
+            expected: lhs.ty(),
+            found: rhs.ty(),
+        });
+    }
+
+    Ok(lhs == rhs)
+}
+
+// Strings can be joined with strings and with numbers, which are written out the same way they
+// are displayed.
+fn concat<T: Numeric>(lhs: &Value<T>, rhs: &Value<T>) -> Result<Value<T>, EvalErrorKind> {
+    let text = |value: &Value<T>| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+
+    match (text(lhs), text(rhs)) {
+        (Some(lhs), Some(rhs)) => Ok(Value::String(lhs + &rhs)),
+        (Some(_), None) => Err(EvalErrorKind::TypeMismatch {
+            expected: ValueType::String,
+            found: rhs.ty(),
+        }),
+        (None, _) => Err(EvalErrorKind::TypeMismatch {
+            expected: ValueType::String,
+            found: lhs.ty(),
+        }),
+    }
+}
+
+fn eval_numeric_op<T: Numeric>(op: Op, lhs: &T, rhs: &T) -> Result<Value<T>, EvalErrorKind> {
+    let result = match op {
+        Op::Lt => return Ok(Value::Bool(lhs < rhs)),
+        Op::Le => return Ok(Value::Bool(lhs <= rhs)),
+        Op::Gt => return Ok(Value::Bool(lhs > rhs)),
+        Op::Ge => return Ok(Value::Bool(lhs >= rhs)),
+        Op::Div | Op::DivEuclid if rhs.is_zero() => return Err(EvalErrorKind::DivisionByZero),
+        Op::RemEuclid if rhs.is_zero() => return Err(EvalErrorKind::ModuloByZero),
+        Op::Add => lhs.checked_add(rhs),
+        Op::Sub => lhs.checked_sub(rhs),
+        Op::Mul => lhs.checked_mul(rhs),
+        Op::Div => lhs.checked_div(rhs),
+        // Both integer division and modulo are Euclidean, so the remainder is never negative and
+        // a == b * (a div b) + a % b holds for any sign of a and b.
+        Op::DivEuclid => lhs.checked_div_euclid(rhs),
+        Op::RemEuclid => lhs.checked_rem_euclid(rhs),
+        Op::Pow => return functions::pow(lhs, rhs).map(Value::Number),
+        Op::Eq | Op::Ne | Op::And | Op::Or | Op::Not => unreachable!(),
+    };
+
+    result.map(Value::Number).ok_or(EvalErrorKind::Overflow)
+}
+
+impl ParenExpr {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        self.expr().ok_or_else(|| incomplete(&self.0))?.eval(ctx)
+    }
+}
+
+impl PrefixExpr {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        let op = self.op().ok_or_else(|| incomplete(&self.0))?;
+        let operand = self.operand().ok_or_else(|| incomplete(&self.0))?;
+
+        let operand_value = operand.eval(ctx)?;
+
+        let result = match (op.into(), &operand_value) {
+            (Op::Add, Value::Number(n)) => Ok(Value::Number(n.clone())),
+            (Op::Sub, Value::Number(n)) => n
+                .checked_neg()
+                .map(Value::Number)
+                .ok_or(EvalErrorKind::Overflow),
+            (Op::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (Op::Not, other) => Err(EvalErrorKind::TypeMismatch {
+                expected: ValueType::Bool,
+                found: other.ty(),
+            }),
+            (_, other) => Err(EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: other.ty(),
+            }),
+        };
+
+        result.map_err(|kind| {
+            EvalError::new(kind, self.0.text_range())
+                .with_operand(operand.text_range(), operand_value)
+        })
+    }
+}
+
+impl CallExpr {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        let callee = self.callee().ok_or_else(|| incomplete(&self.0))?;
+        let arg_list = self.arg_list().ok_or_else(|| incomplete(&self.0))?;
+
+        let callee_value = match &callee {
+            // Names can refer to either a variable or a built-in function, with variables holding
+            // functions taking precedence.
+            Expr::NameRef(name) => match ctx.lookup(name.name()) {
+                Some(Value::Function(closure)) => Value::Function(closure),
+                value => match (ctx.functions.get(name.name()), value) {
+                    (Some(function), _) => {
+                        return self.call_builtin(name, function, &arg_list, ctx);
+                    }
+                    (None, Some(value)) => value,
+                    (None, None) => {
+                        return Err(EvalError::new(
+                            EvalErrorKind::UnknownFunction {
+                                name: name.name().to_string(),
+                            },
+                            name.0.text_range(),
+                        ))
+                    }
+                },
+            },
+            _ => callee.eval(ctx)?,
+        };
+
+        match callee_value {
+            Value::Function(closure) => self.call_closure(&closure, &arg_list, ctx),
+            value => Err(
+                EvalError::new(EvalErrorKind::NotCallable, callee.text_range())
+                    .with_operand(callee.text_range(), value),
+            ),
+        }
+    }
+
+    fn call_builtin<T: Numeric>(
+        &self,
+        name: &NameRef,
+        function: &Function<T>,
+        arg_list: &ArgList,
+        ctx: &Context<'_, T>,
+    ) -> Result<Value<T>, EvalError> {
+        let arg_exprs: Vec<_> = arg_list.args().collect();
+
+        if !function.arity.accepts(arg_exprs.len()) {
+            return Err(EvalError::new(
+                EvalErrorKind::ArityMismatch {
+                    name: Some(name.name().to_string()),
+                    expected: function.arity,
+                    found: arg_exprs.len(),
+                },
+                arg_list.0.text_range(),
+            ));
+        }
+
+        let mut args = Vec::with_capacity(arg_exprs.len());
+
+        for arg in &arg_exprs {
+            let value = arg.eval(ctx)?;
+
+            if function.takes_numbers() && value.ty() != ValueType::Number {
+                return Err(EvalError::new(
+                    EvalErrorKind::TypeMismatch {
+                        expected: ValueType::Number,
+                        found: value.ty(),
+                    },
+                    arg.text_range(),
+                ));
+            }
+
+            args.push(value);
+        }
+
+        let mut call_arg = |index: usize, call_args| match &args[index] {
+            Value::Function(closure) => {
+                self.apply(closure, call_args, arg_exprs[index].text_range(), ctx)
+            }
+            other => Err(type_mismatch(
+                ValueType::Function,
+                other.clone(),
+                &arg_exprs[index],
+            )),
+        };
+
+        function
+            .call(&args, &mut call_arg)
+            .map_err(|error| match error {
+                // We can’t tell which argument the error is about, so show them all.
+                CallError::Builtin(kind) => arg_exprs.iter().zip(&args).fold(
+                    EvalError::new(kind, self.0.text_range()),
+                    |error, (arg, value)| error.with_operand(arg.text_range(), value),
+                ),
+                CallError::Callee(error) => error,
+            })
+    }
+
+    fn call_closure<T: Numeric>(
+        &self,
+        closure: &Closure<T>,
+        arg_list: &ArgList,
+        ctx: &Context<'_, T>,
+    ) -> Result<Value<T>, EvalError> {
+        let args = arg_list
+            .args()
+            .map(|arg| arg.eval(ctx))
+            .collect::<Result<_, _>>()?;
+
+        self.apply(closure, args, arg_list.0.text_range(), ctx)
+    }
+
+    // Calls a closure with arguments that have already been evaluated. Arity mismatches are
+    // reported at args_range.
+    fn apply<T: Numeric>(
+        &self,
+        closure: &Closure<T>,
+        args: Vec<Value<T>>,
+        args_range: TextRange,
+        ctx: &Context<'_, T>,
+    ) -> Result<Value<T>, EvalError> {
+        let lambda = &closure.lambda;
+        let param_list = lambda.param_list().ok_or_else(|| incomplete(&lambda.0))?;
+        let body = lambda.body().ok_or_else(|| incomplete(&lambda.0))?;
+
+        let params: Vec<_> = param_list.params().collect();
+
+        if params.len() != args.len() {
+            return Err(EvalError::new(
+                EvalErrorKind::ArityMismatch {
+                    name: closure.name().map(ToString::to_string),
+                    expected: Arity::Exact(params.len()),
+                    found: args.len(),
+                },
+                args_range,
+            )
+            .with_label(param_list.0.text_range(), "parameters are declared here"));
+        }
+
+        if ctx.calls >= ctx.functions.recursion_limit() {
+            return Err(EvalError::new(
+                EvalErrorKind::RecursionLimit {
+                    limit: ctx.functions.recursion_limit(),
+                },
+                self.0.text_range(),
+            ));
+        }
+
+        // The body sees the variables captured by the closure rather than those at the call site,
+        // plus the closure itself under the name it was bound to so that it can recurse.
+        let mut locals = closure.captured.clone();
+
+        if let Some(name) = &closure.name {
+            locals = locals.bind(name.clone(), Value::Function(closure.clone()));
+        }
 
-        let op = match self.op()?.into() {
-            Op::Add => std::ops::Add::add,
-            Op::Sub => std::ops::Sub::sub,
-            Op::Mul => std::ops::Mul::mul,
-            Op::Div => std::ops::Div::div,
+        for (param, arg) in params.iter().zip(args) {
+            locals = locals.bind(param.text().clone(), arg);
+        }
+
+        let body_ctx = Context {
+            calls: ctx.calls + 1,
+            depth: Cell::new(ctx.depth.get() + 1),
+            ..ctx.with_locals(locals)
+        };
+
+        body.eval(&body_ctx)
+    }
+}
+
+impl Lambda {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        Ok(Value::Function(Closure {
+            lambda: self.clone(),
+            captured: ctx.locals.clone(),
+            name: None,
+        }))
+    }
+}
+
+impl ListExpr {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        self.items()
+            .map(|item| item.eval(ctx))
+            .collect::<Result<_, _>>()
+            .map(Value::List)
+    }
+}
+
+impl IndexExpr {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        let base = self.base().ok_or_else(|| incomplete(&self.0))?;
+        let index = self.index().ok_or_else(|| incomplete(&self.0))?;
+
+        let base_value = base.eval(ctx)?;
+        let index_value = index.eval(ctx)?;
+
+        let result = match (&base_value, &index_value) {
+            (Value::List(items), Value::Number(i)) => match i.to_integer() {
+                Some(i) => usize::try_from(i)
+                    .ok()
+                    .and_then(|i| items.get(i))
+                    .cloned()
+                    .ok_or(EvalErrorKind::IndexOutOfBounds { len: items.len() }),
+                // Negative whole numbers are simply out of bounds.
+                None if i.floor() == *i => {
+                    Err(EvalErrorKind::IndexOutOfBounds { len: items.len() })
+                }
+                None => Err(EvalErrorKind::NonIntegerIndex),
+            },
+            (Value::List(_), other) => Err(EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: other.ty(),
+            }),
+            (other, _) => Err(EvalErrorKind::TypeMismatch {
+                expected: ValueType::List,
+                found: other.ty(),
+            }),
         };
 
-        Some(op(lhs, rhs))
+        result.map_err(|kind| {
+            EvalError::new(kind, self.0.text_range())
+                .with_operand(base.text_range(), base_value)
+                .with_operand(index.text_range(), index_value)
+        })
+    }
+}
+
+impl ConditionalExpr {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        let condition = self.condition().ok_or_else(|| incomplete(&self.0))?;
+        let then_branch = self.then_branch().ok_or_else(|| incomplete(&self.0))?;
+        let else_branch = self.else_branch().ok_or_else(|| incomplete(&self.0))?;
+
+        // Only the branch that is taken gets evaluated.
+        match condition.eval(ctx)? {
+            Value::Bool(true) => then_branch.eval(ctx),
+            Value::Bool(false) => else_branch.eval(ctx),
+            other => Err(EvalError::new(
+                EvalErrorKind::TypeMismatch {
+                    expected: ValueType::Bool,
+                    found: other.ty(),
+                },
+                condition.text_range(),
+            )),
+        }
     }
 }
 
 impl Expr {
-    fn eval(&self) -> Option<u32> {
+    fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        let depth = ctx.depth.get();
+
+        if depth >= MAX_DEPTH {
+            return Err(EvalError::new(
+                EvalErrorKind::NestedTooDeeply,
+                self.text_range(),
+            ));
+        }
+
+        // Calls take the most stack of any expression, so they count double.
+        let cost = if let Self::CallExpr(_) = self { 2 } else { 1 };
+
+        ctx.depth.set(depth + cost);
+        let result = self.eval_unchecked(ctx);
+        ctx.depth.set(depth);
+
+        result
+    }
+
+    fn eval_unchecked<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
         match self {
-            Self::Number(n) => Some(n.eval()),
-            Self::Operation(o) => o.eval(),
+            Self::Number(n) => n.eval(),
+            Self::Str(s) => s.eval(),
+            Self::Boolean(b) => Ok(Value::Bool(b.value())),
+            Self::NameRef(n) => n.eval(ctx),
+            Self::Operation(o) => o.eval(ctx),
+            Self::ParenExpr(p) => p.eval(ctx),
+            Self::PrefixExpr(p) => p.eval(ctx),
+            Self::CallExpr(c) => c.eval(ctx),
+            Self::ConditionalExpr(c) => c.eval(ctx),
+            Self::Lambda(l) => l.eval(ctx),
+            Self::ListExpr(l) => l.eval(ctx),
+            Self::IndexExpr(i) => i.eval(ctx),
+            // The parser has already reported the hole.
+            Self::Missing(m) => Err(incomplete(&m.0)),
         }
     }
 }
 
 impl Root {
-    pub(crate) fn eval(&self) -> Option<u32> {
-        // Roots are expected to include only one child, with that child being an Expr.
-        let expr = Expr::cast(self.0.children_with_tokens().next()?)?;
-        expr.eval()
+    // Each let statement opens a scope covering everything after it, so a binding is visible to
+    // later statements only and shadows (without replacing) any earlier binding of the same name.
+    pub(crate) fn eval<T: Numeric>(&self, ctx: &Context<'_, T>) -> Result<Value<T>, EvalError> {
+        let mut ctx = ctx.with_locals(ctx.locals.clone());
+
+        for stmt in self.stmts() {
+            match stmt {
+                Stmt::Let(stmt) => {
+                    let name = stmt.name().ok_or_else(|| incomplete(&stmt.0))?;
+                    let value_expr = stmt.value().ok_or_else(|| incomplete(&stmt.0))?;
+                    let mut value = value_expr.eval(&ctx)?;
+
+                    // A lambda bound directly with let knows its own name, which lets it call
+                    // itself even though the binding isn’t otherwise visible to its own value.
+                    if let (Expr::Lambda(_), Value::Function(closure)) = (&value_expr, &mut value) {
+                        closure.name = Some(name.text().clone());
+                    }
+
+                    ctx = ctx.with_locals(ctx.locals.bind(name.text().clone(), value));
+                }
+                Stmt::Expr(stmt) => {
+                    // The value is discarded, but errors still stop evaluation.
+                    stmt.expr().ok_or_else(|| incomplete(&stmt.0))?.eval(&ctx)?;
+                }
+            }
+        }
+
+        self.expr().ok_or_else(|| incomplete(&self.0))?.eval(&ctx)
+    }
+}
+
+fn type_mismatch<T>(expected: ValueType, found: Value<T>, expr: &Expr) -> EvalError {
+    EvalError::new(
+        EvalErrorKind::TypeMismatch {
+            expected,
+            found: found.ty(),
+        },
+        expr.text_range(),
+    )
+}
+
+fn incomplete(node: &SyntaxNode) -> EvalError {
+    EvalError::new(EvalErrorKind::IncompleteTree, node.text_range())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Arity, EmptyEnvironment, EvalErrorKind, FunctionRegistry, Parser, Rational, Value,
+        ValueType,
+    };
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use text_size::TextRange;
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+    }
+
+    fn check_error(input: &str, kind: EvalErrorKind, range: TextRange) {
+        let error = Parser::new(input).parse().eval().unwrap_err();
+
+        assert_eq!(error.kind(), &kind);
+        assert_eq!(error.range(), range);
+    }
+
+    #[test]
+    fn evaluate_arithmetic() {
+        assert_eq!(
+            Parser::new(" (1 + 2) * -3 - 8 / 4").parse().eval(),
+            Ok(Value::Number(-11))
+        );
+    }
+
+    #[test]
+    fn evaluate_with_rationals() {
+        assert_eq!(
+            Parser::new("1/3 + 1/6").parse().eval_with::<Rational>(),
+            Ok(Value::Number(Rational::new(1, 2).unwrap())),
+        );
+    }
+
+    #[test]
+    fn evaluate_with_floats() {
+        assert_eq!(
+            Parser::new("7/2").parse().eval_with::<f64>(),
+            Ok(Value::Number(3.5))
+        );
+    }
+
+    #[test]
+    fn evaluate_decimal_literals() {
+        assert_eq!(
+            Parser::new("1.5e3 + .25").parse().eval_with::<f64>(),
+            Ok(Value::Number(1500.25))
+        );
+        assert_eq!(
+            Parser::new("0.1 + 0.2").parse().eval_with::<Rational>(),
+            Ok(Value::Number(Rational::new(3, 10).unwrap())),
+        );
+    }
+
+    #[test]
+    fn evaluate_radix_literals() {
+        assert_eq!(
+            Parser::new("0xff + 0o17 + 0b1010 + 1_000").parse().eval(),
+            Ok(Value::Number(255 + 15 + 10 + 1000)),
+        );
+    }
+
+    #[test]
+    fn fractional_literal_is_an_error_for_integers() {
+        check_error("1 + 2.5", EvalErrorKind::LiteralOutOfRange, range(4, 7));
+    }
+
+    #[test]
+    fn unsigned_subtraction_below_zero_is_an_error() {
+        let error = Parser::new("1-2").parse().eval_with::<u64>().unwrap_err();
+        assert_eq!(error.kind(), &EvalErrorKind::Overflow);
+    }
+
+    #[test]
+    fn literal_range_depends_on_numeric_type() {
+        let parse = Parser::new("18446744073709551615").parse();
+
+        assert_eq!(parse.eval_with::<u64>(), Ok(Value::Number(u64::MAX)));
+        assert_eq!(
+            parse.eval().unwrap_err().kind(),
+            &EvalErrorKind::LiteralOutOfRange,
+        );
+    }
+
+    #[test]
+    fn float_division_by_zero_is_an_error() {
+        let error = Parser::new("1/0").parse().eval_with::<f64>().unwrap_err();
+        assert_eq!(error.kind(), &EvalErrorKind::DivisionByZero);
+    }
+
+    #[test]
+    fn evaluate_variables_from_environment() {
+        let mut env = HashMap::new();
+        env.insert("price", 25_i64);
+        env.insert("qty", 4_i64);
+
+        assert_eq!(
+            Parser::new("price * qty").parse().eval_in(&env),
+            Ok(Value::Number(100))
+        );
+    }
+
+    #[test]
+    fn unbound_variable_is_an_error() {
+        let mut env = HashMap::new();
+        env.insert("x".to_string(), 1_i64);
+
+        let error = Parser::new("x + y").parse().eval_in(&env).unwrap_err();
+
+        assert_eq!(
+            error.kind(),
+            &EvalErrorKind::UnboundVariable {
+                name: "y".to_string(),
+            },
+        );
+        assert_eq!(error.range(), range(4, 5));
+        assert_eq!(error.to_string(), "use of undefined name `y`");
+    }
+
+    #[test]
+    fn variables_are_unbound_without_environment() {
+        check_error(
+            "1 + foo",
+            EvalErrorKind::UnboundVariable {
+                name: "foo".to_string(),
+            },
+            range(4, 7),
+        );
+    }
+
+    #[test]
+    fn evaluate_builtin_functions() {
+        assert_eq!(
+            Parser::new("max(1, abs(-7), 3) + pow(2, 3) - sqrt(16) + floor(5)")
+                .parse()
+                .eval(),
+            Ok(Value::Number(7 + 8 - 4 + 5)),
+        );
+    }
+
+    #[test]
+    fn evaluate_user_registered_functions() {
+        let mut functions = FunctionRegistry::new();
+        functions.register("double", Arity::Exact(1), |args| Ok(args[0] * 2.0));
+
+        assert_eq!(
+            Parser::new("double(1.25)")
+                .parse()
+                .eval_with_functions(&EmptyEnvironment, &functions),
+            Ok(Value::Number(2.5)),
+        );
+    }
+
+    #[test]
+    fn unknown_function_is_an_error() {
+        check_error(
+            "1 + frobnicate(2)",
+            EvalErrorKind::UnknownFunction {
+                name: "frobnicate".to_string(),
+            },
+            range(4, 14),
+        );
+    }
+
+    #[test]
+    fn arity_mismatch_is_reported_on_argument_list() {
+        check_error(
+            "pow(2, 3, 4)",
+            EvalErrorKind::ArityMismatch {
+                name: Some("pow".to_string()),
+                expected: Arity::Exact(2),
+                found: 3,
+            },
+            range(3, 12),
+        );
+    }
+
+    #[test]
+    fn calling_a_non_name_is_an_error() {
+        check_error("(1 + 1)(1)", EvalErrorKind::NotCallable, range(0, 7));
+    }
+
+    #[test]
+    fn exponentiation_binds_tighter_than_negation() {
+        assert_eq!(Parser::new("-2^2").parse().eval(), Ok(Value::Number(-4)));
+        assert_eq!(Parser::new("(-2)^2").parse().eval(), Ok(Value::Number(4)));
+    }
+
+    #[test]
+    fn exponentiation_is_right_associative() {
+        assert_eq!(Parser::new("2^3^2").parse().eval(), Ok(Value::Number(512)));
+    }
+
+    #[test]
+    fn zero_with_any_exponent_is_zero() {
+        assert_eq!(Parser::new("0e-50").parse().eval(), Ok(Value::Number(0)));
+        assert_eq!(
+            Parser::new("0e-50").parse().eval_with::<Rational>(),
+            Ok(Value::Number(Rational::from(0))),
+        );
+    }
+
+    #[test]
+    fn negative_exponent_is_an_error_for_integers() {
+        check_error("2 ^ -1", EvalErrorKind::NegativeExponent, range(0, 6));
+    }
+
+    #[test]
+    fn huge_exponents_of_zero_and_one_are_evaluated() {
+        assert_eq!(
+            Parser::new("0 ^ 5000000000")
+                .parse()
+                .eval_with::<Rational>(),
+            Ok(Value::Number(Rational::from(0))),
+        );
+        assert_eq!(
+            Parser::new("1 ^ 9223372036854775807")
+                .parse()
+                .eval_with::<Rational>(),
+            Ok(Value::Number(Rational::from(1))),
+        );
+    }
+
+    #[test]
+    fn overflowing_exponentiation_is_an_error() {
+        check_error("3 * 2^63", EvalErrorKind::Overflow, range(4, 8));
+    }
+
+    #[test]
+    fn integer_division_and_modulo_are_euclidean() {
+        let eval = |input| Parser::new(input).parse().eval();
+
+        assert_eq!(eval("7 div 2"), Ok(Value::Number(3)));
+        assert_eq!(eval("7 % 2"), Ok(Value::Number(1)));
+        assert_eq!(eval("-7 div 2"), Ok(Value::Number(-4)));
+        assert_eq!(eval("-7 % 2"), Ok(Value::Number(1)));
+        assert_eq!(eval("7 div -2"), Ok(Value::Number(-3)));
+        assert_eq!(eval("7 % -2"), Ok(Value::Number(1)));
+        assert_eq!(eval("-7 div -2"), Ok(Value::Number(4)));
+        assert_eq!(eval("-7 % -2"), Ok(Value::Number(1)));
+    }
+
+    #[test]
+    fn rational_modulo_is_euclidean() {
+        assert_eq!(
+            Parser::new("-7/2 % 2").parse().eval_with::<Rational>(),
+            Ok(Value::Number(Rational::new(1, 2).unwrap())),
+        );
+        assert_eq!(
+            Parser::new("-7/2 div 2").parse().eval_with::<Rational>(),
+            Ok(Value::Number(Rational::from(-2))),
+        );
+    }
+
+    #[test]
+    fn modulo_by_zero_is_an_error() {
+        check_error("10 % (5 - 5)", EvalErrorKind::ModuloByZero, range(0, 12));
+    }
+
+    #[test]
+    fn integer_division_by_zero_is_an_error() {
+        check_error("10 div 0", EvalErrorKind::DivisionByZero, range(0, 8));
+    }
+
+    #[test]
+    fn subtraction_below_zero_is_negative() {
+        assert_eq!(Parser::new("1-2").parse().eval(), Ok(Value::Number(-1)));
+    }
+
+    #[test]
+    fn division_by_zero_is_an_error() {
+        check_error("1 + 1/0", EvalErrorKind::DivisionByZero, range(4, 7));
+    }
+
+    #[test]
+    fn division_by_zero_diagnostic_labels_operands() {
+        let diagnostic = Parser::new("6 / (2-2)")
+            .parse()
+            .eval()
+            .unwrap_err()
+            .as_diagnostic(());
+        let labels: Vec<_> = diagnostic
+            .labels
+            .iter()
+            .map(|label| (label.range.clone(), label.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                (0..9, "division by zero"),
+                (0..1, "this evaluates to 6"),
+                (4..9, "this evaluates to 0"),
+            ],
+        );
+    }
+
+    #[test]
+    fn overflowing_multiplication_is_an_error() {
+        check_error(
+            "9223372036854775807*2",
+            EvalErrorKind::Overflow,
+            range(0, 21),
+        );
+    }
+
+    #[test]
+    fn negating_minimum_value_is_an_error() {
+        check_error(
+            "-(-9223372036854775807-1)",
+            EvalErrorKind::Overflow,
+            range(0, 25),
+        );
+    }
+
+    #[test]
+    fn literal_that_does_not_fit_is_an_error() {
+        check_error(
+            "1 + 99999999999999999999",
+            EvalErrorKind::LiteralOutOfRange,
+            range(4, 24),
+        );
+    }
+
+    #[test]
+    fn operation_without_rhs_is_incomplete() {
+        check_error("1 +", EvalErrorKind::IncompleteTree, range(3, 3));
+    }
+
+    #[test]
+    fn missing_operand_is_reported_at_the_hole() {
+        check_error("1 + * 2", EvalErrorKind::IncompleteTree, range(4, 4));
+    }
+
+    #[test]
+    fn empty_input_is_incomplete() {
+        check_error("", EvalErrorKind::IncompleteTree, range(0, 0));
+    }
+
+    #[test]
+    fn evaluate_comparisons() {
+        let eval = |input| Parser::new(input).parse().eval();
+
+        assert_eq!(eval("1 + 1 == 2"), Ok(Value::Bool(true)));
+        assert_eq!(eval("1 != 1"), Ok(Value::Bool(false)));
+        assert_eq!(eval("2 * 3 < 7"), Ok(Value::Bool(true)));
+        assert_eq!(eval("7 <= 6"), Ok(Value::Bool(false)));
+        assert_eq!(eval("-1 > -2"), Ok(Value::Bool(true)));
+        assert_eq!(eval("3 >= 3"), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn comparisons_use_numeric_type() {
+        let parse = Parser::new("0.1 + 0.2 == 0.3").parse();
+
+        assert_eq!(parse.eval_with::<Rational>(), Ok(Value::Bool(true)));
+        assert_eq!(parse.eval_with::<f64>(), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn booleans_can_be_compared_for_equality() {
+        assert_eq!(
+            Parser::new("(1 < 2) == (3 < 4)").parse().eval(),
+            Ok(Value::Bool(true)),
+        );
+    }
+
+    #[test]
+    fn booleans_can_be_bound_in_environment() {
+        let mut env = HashMap::new();
+        env.insert("verbose", Value::Bool(true));
+        env.insert("level", Value::Number(2_i64));
+
+        assert_eq!(
+            Parser::new("verbose == (level > 1)")
+                .parse()
+                .eval_in::<i64>(&env),
+            Ok(Value::Bool(true)),
+        );
+    }
+
+    #[test]
+    fn arithmetic_on_booleans_is_a_type_error() {
+        check_error(
+            "(1 < 2) + 1",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::Bool,
+            },
+            range(0, 11),
+        );
+    }
+
+    #[test]
+    fn ordering_booleans_is_a_type_error() {
+        check_error(
+            "1 < (2 < 3)",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::Bool,
+            },
+            range(0, 11),
+        );
+    }
+
+    #[test]
+    fn comparing_numbers_with_booleans_is_a_type_error() {
+        check_error(
+            "1 == (1 == 1)",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::Bool,
+            },
+            range(0, 13),
+        );
+    }
+
+    #[test]
+    fn negating_a_boolean_is_a_type_error() {
+        check_error(
+            "-(1 < 2)",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::Bool,
+            },
+            range(0, 8),
+        );
+    }
+
+    #[test]
+    fn passing_a_boolean_to_a_function_is_a_type_error() {
+        check_error(
+            "abs(1 > 0)",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::Bool,
+            },
+            range(4, 9),
+        );
+    }
+
+    #[test]
+    fn type_mismatch_diagnostic_labels_operands() {
+        let diagnostic = Parser::new("(1 < 2) * 3")
+            .parse()
+            .eval()
+            .unwrap_err()
+            .as_diagnostic(());
+
+        let labels: Vec<_> = diagnostic
+            .labels
+            .iter()
+            .map(|label| (label.range.clone(), label.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                (0..11, "expected a number, found a boolean"),
+                (0..7, "this evaluates to true"),
+                (10..11, "this evaluates to 3"),
+            ],
+        );
+    }
+
+    #[test]
+    fn evaluate_boolean_literals() {
+        assert_eq!(Parser::new("true").parse().eval(), Ok(Value::Bool(true)));
+        assert_eq!(
+            Parser::new("false == false").parse().eval(),
+            Ok(Value::Bool(true)),
+        );
+    }
+
+    #[test]
+    fn evaluate_logical_operators() {
+        let eval = |input| Parser::new(input).parse().eval();
+
+        assert_eq!(eval("true && false"), Ok(Value::Bool(false)));
+        assert_eq!(eval("false || true"), Ok(Value::Bool(true)));
+        assert_eq!(eval("!false"), Ok(Value::Bool(true)));
+        assert_eq!(eval("1 < 2 && 3 > 4 || !(5 == 6)"), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        let eval = |input| Parser::new(input).parse().eval();
+
+        assert_eq!(eval("false && 1/0 == 1"), Ok(Value::Bool(false)));
+        assert_eq!(eval("true || 1/0 == 1"), Ok(Value::Bool(true)));
+        assert_eq!(eval("false && 1"), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn right_operand_is_evaluated_when_needed() {
+        check_error(
+            "true && 1/0 == 1",
+            EvalErrorKind::DivisionByZero,
+            range(8, 12),
+        );
+    }
+
+    #[test]
+    fn logical_operator_on_number_is_a_type_error() {
+        check_error(
+            "1 && true",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Bool,
+                found: ValueType::Number,
+            },
+            range(0, 9),
+        );
+        check_error(
+            "false || 2",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Bool,
+                found: ValueType::Number,
+            },
+            range(0, 10),
+        );
+    }
+
+    #[test]
+    fn not_on_number_is_a_type_error() {
+        check_error(
+            "!0",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Bool,
+                found: ValueType::Number,
+            },
+            range(0, 2),
+        );
+    }
+
+    #[test]
+    fn evaluate_conditionals() {
+        let mut env = HashMap::new();
+        env.insert("qty", 12_i64);
+
+        assert_eq!(
+            Parser::new("if qty > 10 then 5 else 7")
+                .parse()
+                .eval_in(&env),
+            Ok(Value::Number(5)),
+        );
+        assert_eq!(
+            Parser::new("if qty > 20 then 5 else if qty > 10 then 6 else 7")
+                .parse()
+                .eval_in(&env),
+            Ok(Value::Number(6)),
+        );
+    }
+
+    #[test]
+    fn only_the_taken_branch_is_evaluated() {
+        let eval = |input| Parser::new(input).parse().eval();
+
+        assert_eq!(eval("if true then 1 else 1/0"), Ok(Value::Number(1)));
+        assert_eq!(eval("if false then 1/0 else 2"), Ok(Value::Number(2)));
+    }
+
+    #[test]
+    fn non_boolean_condition_is_a_type_error() {
+        check_error(
+            "if 1 then 2 else 3",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Bool,
+                found: ValueType::Number,
+            },
+            range(3, 4),
+        );
+    }
+
+    #[test]
+    fn conditional_without_else_is_incomplete() {
+        check_error(
+            "if true then 1",
+            EvalErrorKind::IncompleteTree,
+            range(0, 14),
+        );
+    }
+
+    #[test]
+    fn evaluate_let_bindings() {
+        assert_eq!(
+            Parser::new("let x = 3; let y = x * 2; y + 1")
+                .parse()
+                .eval(),
+            Ok(Value::Number(7)),
+        );
+    }
+
+    #[test]
+    fn later_bindings_shadow_earlier_ones() {
+        assert_eq!(
+            Parser::new("let x = 1; let y = x; let x = x + 10; x * 100 + y")
+                .parse()
+                .eval(),
+            Ok(Value::Number(1101)),
+        );
+    }
+
+    #[test]
+    fn long_statement_lists_are_evaluated() {
+        let input = format!("{}x", "let x = 1;\n".repeat(50_000));
+
+        assert_eq!(Parser::new(&input).parse().eval(), Ok(Value::Number(1)));
+    }
+
+    #[test]
+    fn bindings_shadow_environment() {
+        let mut env = HashMap::new();
+        env.insert("rate", 2_i64);
+
+        assert_eq!(
+            Parser::new("let rate = rate * 3; rate")
+                .parse()
+                .eval_in(&env),
+            Ok(Value::Number(6)),
+        );
+    }
+
+    #[test]
+    fn bindings_can_hold_booleans() {
+        assert_eq!(
+            Parser::new("let big = 5 > 3; if big then 1 else 0")
+                .parse()
+                .eval(),
+            Ok(Value::Number(1)),
+        );
+    }
+
+    #[test]
+    fn using_a_name_before_its_binding_is_an_error() {
+        check_error(
+            "let x = y; let y = 1; x",
+            EvalErrorKind::UnboundVariable {
+                name: "y".to_string(),
+            },
+            range(8, 9),
+        );
+    }
+
+    #[test]
+    fn binding_is_not_visible_in_its_own_value() {
+        check_error(
+            "let x = x + 1; x",
+            EvalErrorKind::UnboundVariable {
+                name: "x".to_string(),
+            },
+            range(8, 9),
+        );
+    }
+
+    #[test]
+    fn expression_statements_are_evaluated() {
+        check_error("1/0; 2", EvalErrorKind::DivisionByZero, range(0, 3));
+        assert_eq!(Parser::new("1 + 1; 2").parse().eval(), Ok(Value::Number(2)),);
+    }
+
+    #[test]
+    fn program_without_final_expression_is_incomplete() {
+        check_error("let x = 1;", EvalErrorKind::IncompleteTree, range(10, 10));
+    }
+
+    #[test]
+    fn evaluate_lambdas() {
+        assert_eq!(
+            Parser::new("let sq = fn(x) x * x; sq(4)").parse().eval(),
+            Ok(Value::Number(16)),
+        );
+        assert_eq!(
+            Parser::new("(fn(a, b) a - b)(10, 3)").parse().eval(),
+            Ok(Value::Number(7)),
+        );
+    }
+
+    #[test]
+    fn closures_capture_variables_where_they_are_created() {
+        assert_eq!(
+            Parser::new("let k = 3; let add = fn(x) x + k; let k = 100; add(1)")
+                .parse()
+                .eval(),
+            Ok(Value::Number(4)),
+        );
+    }
+
+    #[test]
+    fn functions_are_values() {
+        assert_eq!(
+            Parser::new("let twice = fn(f, x) f(f(x)); twice(fn(x) x * 2, 3)")
+                .parse()
+                .eval(),
+            Ok(Value::Number(12)),
+        );
+        assert_eq!(
+            Parser::new("let adder = fn(n) fn(x) x + n; adder(2)(40)")
+                .parse()
+                .eval(),
+            Ok(Value::Number(42)),
+        );
+    }
+
+    #[test]
+    fn let_bound_lambdas_can_recurse() {
+        assert_eq!(
+            Parser::new("let fact = fn(n) if n <= 1 then 1 else n * fact(n - 1); fact(10)")
+                .parse()
+                .eval(),
+            Ok(Value::Number(3628800)),
+        );
+    }
+
+    #[test]
+    fn variables_shadow_builtin_functions() {
+        assert_eq!(
+            Parser::new("let max = fn(a, b) a; max(1, 2)")
+                .parse()
+                .eval(),
+            Ok(Value::Number(1)),
+        );
+    }
+
+    #[test]
+    fn runaway_recursion_is_an_error() {
+        check_error(
+            "let f = fn(n) f(n + 1); f(0)",
+            EvalErrorKind::RecursionLimit { limit: 100 },
+            range(14, 22),
+        );
+    }
+
+    #[test]
+    fn deep_nesting_inside_recursion_is_an_error() {
+        let parens = |n| {
+            format!(
+                "let f = fn(n) if n == 0 then 0 else {}f(n - 1){}; f(255)",
+                "(".repeat(n),
+                ")".repeat(n),
+            )
+        };
+
+        let mut functions = FunctionRegistry::new();
+        functions.set_recursion_limit(1000);
+
+        for input in [parens(40), parens(100)] {
+            let error = Parser::new(&input)
+                .parse()
+                .eval_with_functions::<i64>(&EmptyEnvironment, &functions)
+                .unwrap_err();
+
+            assert_eq!(error.kind(), &EvalErrorKind::NestedTooDeeply);
+        }
+    }
+
+    #[test]
+    fn deepest_nesting_the_parser_accepts_can_be_evaluated() {
+        let input = format!("{}1{}", "(".repeat(255), ")".repeat(255));
+
+        assert_eq!(Parser::new(&input).parse().eval(), Ok(Value::Number(1)));
+    }
+
+    #[test]
+    fn recursion_limit_is_configurable() {
+        let mut functions = FunctionRegistry::new();
+        functions.set_recursion_limit(5);
+
+        let eval = |input: &str| {
+            Parser::new(input)
+                .parse()
+                .eval_with_functions::<i64>(&EmptyEnvironment, &functions)
+        };
+
+        let countdown = "let f = fn(n) if n == 0 then 0 else f(n - 1);";
+
+        assert_eq!(eval(&format!("{} f(4)", countdown)), Ok(Value::Number(0)));
+        assert_eq!(
+            eval(&format!("{} f(5)", countdown)).unwrap_err().kind(),
+            &EvalErrorKind::RecursionLimit { limit: 5 },
+        );
+    }
+
+    #[test]
+    fn lambda_arity_mismatch_is_an_error() {
+        check_error(
+            "let sq = fn(x) x * x; sq(1, 2)",
+            EvalErrorKind::ArityMismatch {
+                name: Some("sq".to_string()),
+                expected: Arity::Exact(1),
+                found: 2,
+            },
+            range(24, 30),
+        );
+        check_error(
+            "(fn() 1)(2)",
+            EvalErrorKind::ArityMismatch {
+                name: None,
+                expected: Arity::Exact(0),
+                found: 1,
+            },
+            range(8, 11),
+        );
+    }
+
+    #[test]
+    fn lambda_arity_mismatch_diagnostic_points_at_parameters() {
+        let diagnostic = Parser::new("let sq = fn(x) x * x; sq()")
+            .parse()
+            .eval()
+            .unwrap_err()
+            .as_diagnostic(());
+
+        let labels: Vec<_> = diagnostic
+            .labels
+            .iter()
+            .map(|label| (label.range.clone(), label.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                (24..26, "`sq` takes 1 argument but 0 were supplied"),
+                (11..15, "parameters are declared here"),
+            ],
+        );
+    }
+
+    #[test]
+    fn calling_a_number_is_an_error() {
+        let mut env = HashMap::new();
+        env.insert("x", 5_i64);
+
+        let error = Parser::new("x(1)").parse().eval_in(&env).unwrap_err();
+
+        assert_eq!(error.kind(), &EvalErrorKind::NotCallable);
+        assert_eq!(error.range(), range(0, 1));
+    }
+
+    #[test]
+    fn functions_display_as_their_source() {
+        assert_eq!(
+            Parser::new("fn(x) x + 1 ")
+                .parse()
+                .eval()
+                .unwrap()
+                .to_string(),
+            "fn(x) x + 1",
+        );
+    }
+
+    fn list(items: &[i64]) -> Value {
+        Value::List(items.iter().copied().map(Value::Number).collect())
+    }
+
+    #[test]
+    fn evaluate_list_literals() {
+        assert_eq!(
+            Parser::new("[1, 1 + 1, 3,]").parse().eval(),
+            Ok(list(&[1, 2, 3]))
+        );
+        assert_eq!(Parser::new("[]").parse().eval(), Ok(list(&[])));
+        assert_eq!(
+            Parser::new("[[1], [true]]").parse().eval(),
+            Ok(Value::List(vec![
+                list(&[1]),
+                Value::List(vec![Value::Bool(true)]),
+            ])),
+        );
+    }
+
+    #[test]
+    fn lists_can_be_compared_for_equality() {
+        assert_eq!(
+            Parser::new("[1, 2] == [1, 2]").parse().eval(),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(
+            Parser::new("[1, 2] != [2, 1]").parse().eval(),
+            Ok(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn lists_display_their_items() {
+        assert_eq!(
+            Parser::new("[1, [2, 3], true]")
+                .parse()
+                .eval()
+                .unwrap()
+                .to_string(),
+            "[1, [2, 3], true]",
+        );
+    }
+
+    #[test]
+    fn evaluate_indexing() {
+        assert_eq!(
+            Parser::new("[4, 5, 6][2]").parse().eval(),
+            Ok(Value::Number(6))
+        );
+        assert_eq!(
+            Parser::new("let xs = [[1], [2, 3]]; xs[1][0]")
+                .parse()
+                .eval(),
+            Ok(Value::Number(2))
+        );
+        assert_eq!(
+            Parser::new("[4, 5, 6][1.0]").parse().eval_with::<f64>(),
+            Ok(Value::Number(5.0))
+        );
+    }
+
+    #[test]
+    fn index_out_of_bounds_is_an_error() {
+        check_error(
+            "[1, 2][2]",
+            EvalErrorKind::IndexOutOfBounds { len: 2 },
+            range(0, 9),
+        );
+        check_error(
+            "[1, 2][-1]",
+            EvalErrorKind::IndexOutOfBounds { len: 2 },
+            range(0, 10),
+        );
+    }
+
+    #[test]
+    fn fractional_index_is_an_error() {
+        let error = Parser::new("[1, 2][0.5]")
+            .parse()
+            .eval_with::<f64>()
+            .unwrap_err();
+
+        assert_eq!(error.kind(), &EvalErrorKind::NonIntegerIndex);
+        assert_eq!(error.range(), range(0, 11));
+    }
+
+    #[test]
+    fn indexing_a_number_is_a_type_error() {
+        check_error(
+            "5[0]",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::List,
+                found: ValueType::Number,
+            },
+            range(0, 4),
+        );
+        check_error(
+            "[1][true]",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::Bool,
+            },
+            range(0, 9),
+        );
+    }
+
+    #[test]
+    fn index_error_diagnostic_labels_list_and_index() {
+        let diagnostic = Parser::new("[1, 2][5]")
+            .parse()
+            .eval()
+            .unwrap_err()
+            .as_diagnostic(());
+
+        let labels: Vec<_> = diagnostic
+            .labels
+            .iter()
+            .map(|label| (label.range.clone(), label.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                (0..9, "index out of bounds for a list of length 2"),
+                (0..6, "this evaluates to [1, 2]"),
+                (7..8, "this evaluates to 5"),
+            ],
+        );
+    }
+
+    #[test]
+    fn evaluate_aggregates() {
+        assert_eq!(
+            Parser::new("sum([1, 2, 3])").parse().eval(),
+            Ok(Value::Number(6))
+        );
+        assert_eq!(
+            Parser::new("avg([1, 2])").parse().eval_with::<f64>(),
+            Ok(Value::Number(1.5))
+        );
+        assert_eq!(
+            Parser::new("len([1, true, []])").parse().eval(),
+            Ok(Value::Number(3))
+        );
+    }
+
+    #[test]
+    fn aggregate_of_non_list_is_a_type_error() {
+        check_error(
+            "sum(1)",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::List,
+                found: ValueType::Number,
+            },
+            range(0, 6),
+        );
+        check_error(
+            "sum([1, true])",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::Bool,
+            },
+            range(0, 14),
+        );
+    }
+
+    #[test]
+    fn passing_a_list_to_a_numeric_function_is_a_type_error() {
+        check_error(
+            "abs([1])",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::List,
+            },
+            range(4, 7),
+        );
+    }
+
+    #[test]
+    fn evaluate_map() {
+        assert_eq!(
+            Parser::new("map([1, 2, 3], fn(x) x * x)").parse().eval(),
+            Ok(list(&[1, 4, 9]))
+        );
+        assert_eq!(
+            Parser::new("let n = 10; sum(map([1, 2], fn(x) x + n))")
+                .parse()
+                .eval(),
+            Ok(Value::Number(23))
+        );
+    }
+
+    #[test]
+    fn map_checks_its_arguments() {
+        check_error(
+            "map([1])",
+            EvalErrorKind::ArityMismatch {
+                name: Some("map".to_string()),
+                expected: Arity::Exact(2),
+                found: 1,
+            },
+            range(3, 8),
+        );
+        check_error(
+            "map(1, fn(x) x)",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::List,
+                found: ValueType::Number,
+            },
+            range(0, 15),
+        );
+        check_error(
+            "map([1], 2)",
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Function,
+                found: ValueType::Number,
+            },
+            range(0, 11),
+        );
+        check_error(
+            "map([1], fn(x, y) x)",
+            EvalErrorKind::ArityMismatch {
+                name: None,
+                expected: Arity::Exact(2),
+                found: 1,
+            },
+            range(9, 19),
+        );
+    }
+
+    #[test]
+    fn map_is_unknown_without_the_builtins() {
+        let error = Parser::new("map([1], fn(x) x)")
+            .parse()
+            .eval_with_functions(&EmptyEnvironment, &FunctionRegistry::<i64>::empty())
+            .unwrap_err();
+
+        assert_eq!(
+            error.kind(),
+            &EvalErrorKind::UnknownFunction {
+                name: "map".to_string(),
+            },
+        );
+    }
+
+    #[test]
+    fn evaluate_string_literals() {
+        assert_eq!(
+            Parser::new(r#""say \"hi\"\n""#).parse().eval(),
+            Ok(Value::String("say \"hi\"\n".to_string()))
+        );
+    }
+
+    #[test]
+    fn strings_concatenate_with_strings_and_numbers() {
+        assert_eq!(
+            Parser::new(r#""Total: " + (1 + 2)"#).parse().eval(),
+            Ok(Value::String("Total: 3".to_string()))
+        );
+        assert_eq!(
+            Parser::new(r#"1 + 2 + " items""#).parse().eval(),
+            Ok(Value::String("3 items".to_string()))
+        );
+        assert_eq!(
+            Parser::new(r#""a" + "b""#).parse().eval(),
+            Ok(Value::String("ab".to_string()))
+        );
+    }
+
+    #[test]
+    fn numbers_are_concatenated_as_displayed() {
+        assert_eq!(
+            Parser::new(r#""half: " + 1/2"#)
+                .parse()
+                .eval_with::<Rational>(),
+            Ok(Value::String("half: 1/2".to_string()))
+        );
+    }
+
+    #[test]
+    fn concatenating_other_types_is_a_type_error() {
+        check_error(
+            r#""a" + true"#,
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::String,
+                found: ValueType::Bool,
+            },
+            range(0, 10),
+        );
+        check_error(
+            r#"[1] + "a""#,
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::String,
+                found: ValueType::List,
+            },
+            range(0, 9),
+        );
+    }
+
+    #[test]
+    fn other_operators_on_strings_are_type_errors() {
+        check_error(
+            r#""a" * 2"#,
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::String,
+            },
+            range(0, 7),
+        );
+        check_error(
+            r#"-"a""#,
+            EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::String,
+            },
+            range(0, 4),
+        );
+    }
+
+    #[test]
+    fn strings_can_be_compared_for_equality() {
+        assert_eq!(
+            Parser::new(r#""a" + "b" == "ab""#).parse().eval(),
+            Ok(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn strings_display_quoted() {
+        assert_eq!(
+            Parser::new(r#"["a\n", 1]"#)
+                .parse()
+                .eval()
+                .unwrap()
+                .to_string(),
+            r#"["a\n", 1]"#,
+        );
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        assert_eq!(
+            Parser::new("// config\nlet rate = 2; /* per hour */\nrate * /* hours */ 8")
+                .parse()
+                .eval(),
+            Ok(Value::Number(16))
+        );
     }
 }
@@ -0,0 +1,114 @@
+use crate::value::Value;
+use smol_str::SmolStr;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+use std::rc::Rc;
+
+// Supplies the values of variables referenced by an expression.
+pub trait Environment<T> {
+    fn lookup(&self, name: &str) -> Option<Value<T>>;
+}
+
+// Maps can hold either plain numbers or Values, so that booleans can be bound too.
+impl<T, V: Clone + Into<Value<T>>, S: BuildHasher> Environment<T> for HashMap<String, V, S> {
+    fn lookup(&self, name: &str) -> Option<Value<T>> {
+        self.get(name).cloned().map(Into::into)
+    }
+}
+
+impl<T, V: Clone + Into<Value<T>>, S: BuildHasher> Environment<T> for HashMap<&str, V, S> {
+    fn lookup(&self, name: &str) -> Option<Value<T>> {
+        self.get(name).cloned().map(Into::into)
+    }
+}
+
+impl<T, V: Clone + Into<Value<T>>> Environment<T> for BTreeMap<String, V> {
+    fn lookup(&self, name: &str) -> Option<Value<T>> {
+        self.get(name).cloned().map(Into::into)
+    }
+}
+
+impl<T, V: Clone + Into<Value<T>>> Environment<T> for BTreeMap<&str, V> {
+    fn lookup(&self, name: &str) -> Option<Value<T>> {
+        self.get(name).cloned().map(Into::into)
+    }
+}
+
+// The variables bound by let statements and function parameters, which shadow the caller-supplied
+// environment. Binding a variable creates a new set of locals sharing the old ones, so closures
+// can cheaply keep hold of the locals that were in scope when they were created.
+pub(crate) struct Locals<T>(Option<Rc<Scope<T>>>);
+
+struct Scope<T> {
+    name: SmolStr,
+    value: Value<T>,
+    parent: Locals<T>,
+}
+
+impl<T> Locals<T> {
+    pub(crate) fn new() -> Self {
+        Self(None)
+    }
+
+    pub(crate) fn bind(&self, name: SmolStr, value: Value<T>) -> Self {
+        Self(Some(Rc::new(Scope {
+            name,
+            value,
+            parent: self.clone(),
+        })))
+    }
+
+    pub(crate) fn lookup(&self, name: &str) -> Option<&Value<T>> {
+        let mut locals = self;
+
+        while let Some(scope) = &locals.0 {
+            if scope.name == name {
+                return Some(&scope.value);
+            }
+
+            locals = &scope.parent;
+        }
+
+        None
+    }
+
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+// Implemented by hand since deriving would require T: Clone.
+impl<T> Clone for Locals<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+// Dropping scopes one at a time, rather than letting each drop its parent in turn, keeps long
+// runs of let statements from overflowing the stack.
+impl<T> Drop for Locals<T> {
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+
+        // Stop at the first scope that is still shared, since whoever shares it keeps its parents
+        // alive too.
+        while let Some(scope) = next {
+            next = Rc::try_unwrap(scope)
+                .ok()
+                .and_then(|mut scope| scope.parent.0.take());
+        }
+    }
+}
+
+// An environment with no variables, used when evaluating without one.
+pub struct EmptyEnvironment;
+
+impl<T> Environment<T> for EmptyEnvironment {
+    fn lookup(&self, _name: &str) -> Option<Value<T>> {
+        None
+    }
+}
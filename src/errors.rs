@@ -1,64 +1,450 @@
+use crate::functions::Arity;
 use crate::lexer::SyntaxKind;
+use crate::value::ValueType;
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use std::fmt;
 use text_size::TextRange;
 
-pub(crate) struct SyntaxError {
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
     pub(crate) kind: SyntaxErrorKind,
     pub(crate) range: TextRange,
+    pub(crate) suggestions: Vec<Suggestion>,
 }
 
 impl SyntaxError {
-    pub(crate) fn as_diagnostic<FileId>(&self, file_id: FileId) -> Diagnostic<FileId> {
-        Diagnostic::error().with_labels(vec![
-            Label::primary(file_id, self.range).with_message(self.kind.to_string())
-        ])
+    pub(crate) fn new(kind: SyntaxErrorKind, range: TextRange) -> Self {
+        Self {
+            kind,
+            range,
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_suggestion(
+        mut self,
+        range: TextRange,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.suggestions.push(Suggestion {
+            range,
+            replacement: replacement.into(),
+        });
+
+        self
+    }
+
+    pub fn kind(&self) -> &SyntaxErrorKind {
+        &self.kind
+    }
+
+    pub fn range(&self) -> TextRange {
+        self.range
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    // Edits that fix the error, each of which can be applied on its own.
+    pub fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
     }
+
+    pub fn as_diagnostic<FileId: Clone>(&self, file_id: FileId) -> Diagnostic<FileId> {
+        let primary =
+            Label::primary(file_id.clone(), self.range).with_message(self.kind.to_string());
+
+        let suggestions = self.suggestions.iter().map(|suggestion| {
+            Label::secondary(file_id.clone(), suggestion.range)
+                .with_message(format!("help: {}", suggestion))
+        });
+
+        let notes = self
+            .kind
+            .note()
+            .map(|note| format!("note: {}", note))
+            .into_iter()
+            .chain(self.kind.help().map(|help| format!("help: {}", help)));
+
+        Diagnostic::error()
+            .with_code(self.code())
+            .with_labels(std::iter::once(primary).chain(suggestions).collect())
+            .with_notes(notes.collect())
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+// A machine-applicable fix: replacing the text in range with replacement. Insertions have an empty
+// range, and removals an empty replacement. Fixes only ever complete or remove what is malformed,
+// never change what the rest of the code means, so they are safe to apply without reading them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub range: TextRange,
+    pub replacement: String,
 }
 
-pub(crate) enum SyntaxErrorKind {
+// Describes the edit, for use as the title of a quick fix.
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.range.is_empty(), self.replacement.is_empty()) {
+            (true, _) => write!(f, "insert `{}`", self.replacement),
+            (false, true) => f.write_str("remove this"),
+            (false, false) => write!(f, "replace this with `{}`", self.replacement),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxErrorKind {
     FoundExpected {
         found: SyntaxKind,
         expected: &'static [SyntaxKind],
     },
+    // Used when what was found is better shown as written than described.
+    FoundTextExpected {
+        found: String,
+        expected: &'static [SyntaxKind],
+    },
     Expected {
         expected: &'static [SyntaxKind],
     },
+    UnclosedParen,
+    MissingFractionDigits,
+    MissingExponentDigits,
+    MissingRadixDigits,
+    InvalidDigit {
+        radix: Radix,
+    },
+    ChainedComparison,
+    UnclosedBracket,
+    UnterminatedString,
+    InvalidEscape,
+    InvalidUnicodeEscape,
+    UnterminatedComment,
+    TrailingInput,
+    NestedTooDeeply,
 }
 
 impl fmt::Display for SyntaxErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let expected_kinds = match self {
+        match self {
             Self::FoundExpected { found, expected } => {
                 write!(f, "found {}, ", found)?;
-                expected
+                write_expected(f, expected)
             }
-            Self::Expected { expected } => expected,
-        };
-
-        let num_expected_kinds = expected_kinds.len();
+            Self::FoundTextExpected { found, expected } => {
+                write!(f, "found `{}`, ", found)?;
+                write_expected(f, expected)
+            }
+            Self::Expected { expected } => write_expected(f, expected),
+            Self::UnclosedParen => f.write_str("unclosed parenthesis"),
+            Self::MissingFractionDigits => f.write_str("expected digits after the decimal point"),
+            Self::MissingExponentDigits => f.write_str("expected digits in the exponent"),
+            Self::MissingRadixDigits => f.write_str("expected digits after the radix prefix"),
+            Self::InvalidDigit { radix } => write!(
+                f,
+                "invalid digit for {} {} literal",
+                match radix {
+                    Radix::Octal => "an",
+                    _ => "a",
+                },
+                radix,
+            ),
+            Self::ChainedComparison => f.write_str("comparison operators cannot be chained"),
+            Self::UnclosedBracket => f.write_str("unclosed bracket"),
+            Self::UnterminatedString => f.write_str("unterminated string"),
+            Self::InvalidEscape => f.write_str("unknown escape sequence"),
+            Self::UnterminatedComment => f.write_str("unterminated block comment"),
+            Self::TrailingInput => f.write_str("unexpected trailing input"),
+            Self::NestedTooDeeply => f.write_str("expression nested too deeply"),
+            Self::InvalidUnicodeEscape => {
+                f.write_str("unicode escapes must be `\\u{...}` with 1 to 6 hex digits")
+            }
+        }
+    }
+}
 
-        f.write_str("expected ")?;
+impl SyntaxErrorKind {
+    // Codes are stable, so once assigned they must never be reused or renumbered.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::FoundExpected { .. } | Self::Expected { .. } => "E0001",
+            Self::FoundTextExpected { .. } => "E0002",
+            Self::UnclosedParen => "E0003",
+            Self::UnclosedBracket => "E0004",
+            Self::MissingFractionDigits => "E0005",
+            Self::MissingExponentDigits => "E0006",
+            Self::MissingRadixDigits => "E0007",
+            Self::InvalidDigit { .. } => "E0008",
+            Self::ChainedComparison => "E0009",
+            Self::UnterminatedString => "E0010",
+            Self::InvalidEscape => "E0011",
+            Self::InvalidUnicodeEscape => "E0012",
+            Self::UnterminatedComment => "E0013",
+            Self::TrailingInput => "E0014",
+            Self::NestedTooDeeply => "E0015",
+        }
+    }
 
-        let is_first = |idx| idx == 0;
-        let is_last = |idx| idx == num_expected_kinds - 1;
+    // Background on why this is an error.
+    pub fn note(&self) -> Option<String> {
+        match self {
+            Self::InvalidDigit { radix } => Some(format!(
+                "{} literals can only contain the digits {}",
+                radix,
+                match radix {
+                    Radix::Binary => "0 and 1",
+                    Radix::Octal => "0 to 7",
+                    Radix::Decimal => "0 to 9",
+                    Radix::Hexadecimal => "0 to 9 and A to F",
+                },
+            )),
+            Self::InvalidEscape => Some(
+                "the supported escapes are `\\n`, `\\r`, `\\t`, `\\0`, `\\\\`, `\\\"` and `\\u{...}`"
+                    .to_string(),
+            ),
+            Self::UnterminatedComment => {
+                Some("block comments nest, so each `/*` needs its own `*/`".to_string())
+            }
+            _ => None,
+        }
+    }
 
-        for (idx, expected_kind) in expected_kinds.iter().enumerate() {
-            match (is_first(idx), is_last(idx)) {
-                (true, _) => write!(f, "{}", expected_kind)?,
-                (false, false) => write!(f, ", {}", expected_kind)?,
-                (false, true) => write!(f, " or {}", expected_kind)?,
+    // How to fix the error, where that can’t be expressed as a suggestion.
+    pub fn help(&self) -> Option<String> {
+        match self {
+            Self::MissingExponentDigits => {
+                Some("the exponent is a power of ten, as in `1e3`".to_string())
+            }
+            Self::MissingRadixDigits => {
+                Some("add digits after the prefix, as in `0x1F`".to_string())
+            }
+            Self::ChainedComparison => {
+                Some("combine comparisons with `&&`, as in `a < b && b < c`".to_string())
+            }
+            Self::InvalidEscape => {
+                Some("to include a backslash itself, escape it as `\\\\`".to_string())
             }
+            _ => None,
         }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+
+impl Radix {
+    pub fn value(self) -> u32 {
+        match self {
+            Self::Binary => 2,
+            Self::Octal => 8,
+            Self::Decimal => 10,
+            Self::Hexadecimal => 16,
+        }
+    }
+}
+
+impl fmt::Display for Radix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Binary => "binary",
+            Self::Octal => "octal",
+            Self::Decimal => "decimal",
+            Self::Hexadecimal => "hexadecimal",
+        })
+    }
+}
+
+fn write_expected(f: &mut fmt::Formatter<'_>, expected_kinds: &[SyntaxKind]) -> fmt::Result {
+    let num_expected_kinds = expected_kinds.len();
+
+    f.write_str("expected ")?;
+
+    let is_first = |idx| idx == 0;
+    let is_last = |idx| idx == num_expected_kinds - 1;
+
+    for (idx, expected_kind) in expected_kinds.iter().enumerate() {
+        match (is_first(idx), is_last(idx)) {
+            (true, _) => write!(f, "{}", expected_kind)?,
+            (false, false) => write!(f, ", {}", expected_kind)?,
+            (false, true) => write!(f, " or {}", expected_kind)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalError {
+    pub(crate) kind: EvalErrorKind,
+    pub(crate) range: TextRange,
+    pub(crate) labels: Vec<SecondaryLabel>,
+}
+
+impl EvalError {
+    pub(crate) fn new(kind: EvalErrorKind, range: TextRange) -> Self {
+        Self {
+            kind,
+            range,
+            labels: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_operand(self, range: TextRange, value: impl fmt::Display) -> Self {
+        self.with_label(range, format!("this evaluates to {}", value))
+    }
+
+    pub(crate) fn with_label(mut self, range: TextRange, message: impl Into<String>) -> Self {
+        self.labels.push(SecondaryLabel {
+            range,
+            message: message.into(),
+        });
+
+        self
+    }
+
+    pub fn kind(&self) -> &EvalErrorKind {
+        &self.kind
+    }
+
+    pub fn range(&self) -> TextRange {
+        self.range
+    }
+
+    pub fn as_diagnostic<FileId: Clone>(&self, file_id: FileId) -> Diagnostic<FileId> {
+        let primary =
+            Label::primary(file_id.clone(), self.range).with_message(self.kind.to_string());
+
+        let secondaries = self.labels.iter().map(|label| {
+            Label::secondary(file_id.clone(), label.range).with_message(label.message.clone())
+        });
+
+        Diagnostic::error().with_labels(std::iter::once(primary).chain(secondaries).collect())
+    }
+}
+
+// Extra context for an evaluation error, such as the values of the operands that led to it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SecondaryLabel {
+    range: TextRange,
+    message: String,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
 
-        Ok(())
+impl std::error::Error for EvalError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalErrorKind {
+    DivisionByZero,
+    ModuloByZero,
+    Overflow,
+    NegativeExponent,
+    LiteralOutOfRange,
+    IncompleteTree,
+    UnboundVariable {
+        name: String,
+    },
+    UnknownFunction {
+        name: String,
+    },
+    NotCallable,
+    TypeMismatch {
+        expected: ValueType,
+        found: ValueType,
+    },
+    // Anonymous functions have no name.
+    ArityMismatch {
+        name: Option<String>,
+        expected: Arity,
+        found: usize,
+    },
+    RecursionLimit {
+        limit: usize,
+    },
+    NestedTooDeeply,
+    IndexOutOfBounds {
+        len: usize,
+    },
+    NonIntegerIndex,
+    Custom {
+        message: String,
+    },
+}
+
+impl fmt::Display for EvalErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DivisionByZero => f.write_str("division by zero"),
+            Self::ModuloByZero => f.write_str("modulo by zero"),
+            Self::Overflow => f.write_str("arithmetic overflow"),
+            Self::NegativeExponent => f.write_str("integers cannot be raised to a negative power"),
+            Self::LiteralOutOfRange => f.write_str("number literal cannot be represented"),
+            Self::IncompleteTree => f.write_str("expression is incomplete"),
+            Self::UnboundVariable { name } => write!(f, "use of undefined name `{}`", name),
+            Self::UnknownFunction { name } => write!(f, "unknown function `{}`", name),
+            Self::NotCallable => f.write_str("only functions can be called"),
+            Self::TypeMismatch { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            Self::ArityMismatch {
+                name,
+                expected,
+                found,
+            } => {
+                match name {
+                    Some(name) => write!(f, "`{}`", name)?,
+                    None => f.write_str("this function")?,
+                }
+
+                write!(
+                    f,
+                    " takes {} but {} {} supplied",
+                    expected,
+                    found,
+                    if *found == 1 { "was" } else { "were" },
+                )
+            }
+            Self::RecursionLimit { limit } => {
+                write!(f, "recursion limit of {} nested calls exceeded", limit)
+            }
+            Self::NestedTooDeeply => f.write_str("expression nested too deeply to evaluate"),
+            Self::IndexOutOfBounds { len } => {
+                write!(f, "index out of bounds for a list of length {}", len)
+            }
+            Self::NonIntegerIndex => f.write_str("list indices must be whole numbers"),
+            Self::Custom { message } => f.write_str(message),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codespan_reporting::diagnostic::LabelStyle;
     use pretty_assertions::assert_eq;
+    use text_size::TextSize;
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(TextSize::from(start), TextSize::from(end))
+    }
 
     #[test]
     fn expected_with_one_kind_has_no_separators() {
@@ -133,4 +519,160 @@ mod tests {
             "found a slash, expected a plus sign, a minus sign or an asterisk",
         );
     }
+
+    #[test]
+    fn found_text_expected_quotes_text() {
+        assert_eq!(
+            SyntaxErrorKind::FoundTextExpected {
+                found: "abc".to_string(),
+                expected: &[SyntaxKind::Number],
+            }
+            .to_string(),
+            "found `abc`, expected a number literal",
+        );
+    }
+
+    #[test]
+    fn unclosed_paren_has_no_expected_kinds() {
+        assert_eq!(
+            SyntaxErrorKind::UnclosedParen.to_string(),
+            "unclosed parenthesis",
+        );
+    }
+
+    #[test]
+    fn invalid_digit_names_radix() {
+        assert_eq!(
+            SyntaxErrorKind::InvalidDigit {
+                radix: Radix::Octal
+            }
+            .to_string(),
+            "invalid digit for an octal literal",
+        );
+    }
+
+    #[test]
+    fn codes_are_stable() {
+        assert_eq!(SyntaxErrorKind::UnclosedParen.code(), "E0003");
+        assert_eq!(
+            SyntaxErrorKind::InvalidDigit {
+                radix: Radix::Hexadecimal
+            }
+            .code(),
+            "E0008"
+        );
+        assert_eq!(SyntaxErrorKind::TrailingInput.code(), "E0014");
+    }
+
+    #[test]
+    fn invalid_digit_note_lists_allowed_digits() {
+        assert_eq!(
+            SyntaxErrorKind::InvalidDigit {
+                radix: Radix::Binary
+            }
+            .note()
+            .as_deref(),
+            Some("binary literals can only contain the digits 0 and 1"),
+        );
+    }
+
+    #[test]
+    fn suggestion_display_describes_edit() {
+        let suggestion = |start, end, replacement: &str| Suggestion {
+            range: range(start, end),
+            replacement: replacement.to_string(),
+        };
+
+        assert_eq!(suggestion(3, 3, ")").to_string(), "insert `)`");
+        assert_eq!(suggestion(3, 5, "").to_string(), "remove this");
+        assert_eq!(suggestion(3, 5, "0").to_string(), "replace this with `0`");
+    }
+
+    #[test]
+    fn syntax_error_diagnostic_has_code_notes_and_suggestions() {
+        let diagnostic = SyntaxError::new(SyntaxErrorKind::UnterminatedComment, range(2, 4))
+            .with_suggestion(range(9, 9), "*/")
+            .as_diagnostic(());
+
+        assert_eq!(diagnostic.code.as_deref(), Some("E0013"));
+        assert_eq!(
+            diagnostic.notes,
+            ["note: block comments nest, so each `/*` needs its own `*/`"],
+        );
+
+        let labels: Vec<_> = diagnostic
+            .labels
+            .iter()
+            .map(|label| (label.style, label.range.clone(), label.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                (LabelStyle::Primary, 2..4, "unterminated block comment"),
+                (LabelStyle::Secondary, 9..9, "help: insert `*/`"),
+            ],
+        );
+    }
+
+    #[test]
+    fn help_is_rendered_as_a_note() {
+        let diagnostic =
+            SyntaxError::new(SyntaxErrorKind::ChainedComparison, range(6, 7)).as_diagnostic(());
+
+        assert_eq!(
+            diagnostic.notes,
+            ["help: combine comparisons with `&&`, as in `a < b && b < c`"],
+        );
+        assert_eq!(diagnostic.labels.len(), 1);
+    }
+
+    #[test]
+    fn arity_mismatch_agrees_with_number_supplied() {
+        assert_eq!(
+            EvalErrorKind::ArityMismatch {
+                name: Some("pow".to_string()),
+                expected: Arity::Exact(2),
+                found: 1,
+            }
+            .to_string(),
+            "`pow` takes 2 arguments but 1 was supplied",
+        );
+    }
+
+    #[test]
+    fn arity_mismatch_of_anonymous_function() {
+        assert_eq!(
+            EvalErrorKind::ArityMismatch {
+                name: None,
+                expected: Arity::Exact(1),
+                found: 0,
+            }
+            .to_string(),
+            "this function takes 1 argument but 0 were supplied",
+        );
+    }
+
+    #[test]
+    fn eval_error_labels_operands_with_their_values() {
+        let diagnostic = EvalError::new(EvalErrorKind::DivisionByZero, range(0, 5))
+            .with_operand(range(0, 1), 1)
+            .with_operand(range(4, 5), 0)
+            .as_diagnostic(());
+
+        let labels: Vec<_> = diagnostic
+            .labels
+            .iter()
+            .map(|label| (label.style, label.range.clone(), label.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                (LabelStyle::Primary, 0..5, "division by zero"),
+                (LabelStyle::Secondary, 0..1, "this evaluates to 1"),
+                (LabelStyle::Secondary, 4..5, "this evaluates to 0"),
+            ],
+        );
+    }
 }
@@ -0,0 +1,502 @@
+use crate::errors::{EvalError, EvalErrorKind};
+use crate::numeric::Numeric;
+use crate::value::{Value, ValueType};
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+// Shared by the `^` operator and the `pow` builtin, so that both report the same errors.
+pub(crate) fn pow<T: Numeric>(base: &T, exponent: &T) -> Result<T, EvalErrorKind> {
+    if !T::NEGATIVE_EXPONENTS && T::from_integer(0).is_some_and(|zero| *exponent < zero) {
+        return Err(EvalErrorKind::NegativeExponent);
+    }
+
+    base.checked_pow(exponent).ok_or(EvalErrorKind::Overflow)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub(crate) fn accepts(self, num_args: usize) -> bool {
+        match self {
+            Self::Exact(n) => num_args == n,
+            Self::AtLeast(n) => num_args >= n,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (prefix, n) = match self {
+            Self::Exact(n) => ("", n),
+            Self::AtLeast(n) => ("at least ", n),
+        };
+
+        let plural = if *n == 1 { "" } else { "s" };
+
+        write!(f, "{}{} argument{}", prefix, n, plural)
+    }
+}
+
+const DEFAULT_RECURSION_LIMIT: usize = 100;
+
+// Calls the function passed as the argument at the given index, for functions such as map that
+// take functions as arguments.
+pub(crate) type CallArg<'a, T> =
+    dyn FnMut(usize, Vec<Value<T>>) -> Result<Value<T>, EvalError> + 'a;
+
+type NumericBody<T> = Box<dyn Fn(&[T]) -> Result<T, EvalErrorKind>>;
+type ValuesBody<T> = Box<dyn Fn(&[Value<T>]) -> Result<Value<T>, EvalErrorKind>>;
+type CallingBody<T> = Box<dyn Fn(&[Value<T>], &mut CallArg<'_, T>) -> Result<Value<T>, CallError>>;
+
+enum Body<T> {
+    Numeric(NumericBody<T>),
+    Values(ValuesBody<T>),
+    Calling(CallingBody<T>),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum CallError {
+    // The call itself failed, such as from being passed the wrong type of argument.
+    Builtin(EvalErrorKind),
+    // A function called by the builtin failed, in which case the error points inside it.
+    Callee(EvalError),
+}
+
+impl From<EvalErrorKind> for CallError {
+    fn from(kind: EvalErrorKind) -> Self {
+        Self::Builtin(kind)
+    }
+}
+
+pub(crate) struct Function<T> {
+    pub(crate) arity: Arity,
+    body: Body<T>,
+}
+
+impl<T: Clone> Function<T> {
+    // Whether every argument must be a number. Callers check this up front so that they can point
+    // at the offending argument.
+    pub(crate) fn takes_numbers(&self) -> bool {
+        matches!(self.body, Body::Numeric(_))
+    }
+
+    // Arity is checked by the caller, so the body can assume it received the right number of
+    // arguments.
+    pub(crate) fn call(
+        &self,
+        args: &[Value<T>],
+        call_arg: &mut CallArg<'_, T>,
+    ) -> Result<Value<T>, CallError> {
+        debug_assert!(self.arity.accepts(args.len()));
+
+        match &self.body {
+            Body::Numeric(body) => {
+                let args = args
+                    .iter()
+                    .map(|arg| expect_number(arg).cloned())
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(body(&args).map(Value::Number)?)
+            }
+            Body::Values(body) => Ok(body(args)?),
+            Body::Calling(body) => body(args, call_arg),
+        }
+    }
+}
+
+// The functions that can be called from an expression, looked up by name.
+pub struct FunctionRegistry<T> {
+    functions: HashMap<String, Function<T>>,
+    recursion_limit: usize,
+}
+
+impl<T> FunctionRegistry<T> {
+    pub fn empty() -> Self {
+        Self {
+            functions: HashMap::new(),
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+        }
+    }
+
+    // Limits how deeply calls to functions defined in the expression itself can nest, so that
+    // runaway recursion becomes an evaluation error. Evaluation also stops once expressions are
+    // nested too deeply overall, which keeps it from overflowing the stack whatever the limit.
+    pub fn set_recursion_limit(&mut self, limit: usize) {
+        self.recursion_limit = limit;
+    }
+
+    pub(crate) fn recursion_limit(&self) -> usize {
+        self.recursion_limit
+    }
+
+    // Registering a function with the same name as an existing one replaces it.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        arity: Arity,
+        body: impl Fn(&[T]) -> Result<T, EvalErrorKind> + 'static,
+    ) {
+        self.functions.insert(
+            name.into(),
+            Function {
+                arity,
+                body: Body::Numeric(Box::new(body)),
+            },
+        );
+    }
+
+    // Like register, but for functions that take or return values other than numbers.
+    pub fn register_values(
+        &mut self,
+        name: impl Into<String>,
+        arity: Arity,
+        body: impl Fn(&[Value<T>]) -> Result<Value<T>, EvalErrorKind> + 'static,
+    ) {
+        self.functions.insert(
+            name.into(),
+            Function {
+                arity,
+                body: Body::Values(Box::new(body)),
+            },
+        );
+    }
+
+    // Like register_values, but for functions that call functions passed to them as arguments.
+    pub(crate) fn register_calling(
+        &mut self,
+        name: impl Into<String>,
+        arity: Arity,
+        body: impl Fn(&[Value<T>], &mut CallArg<'_, T>) -> Result<Value<T>, CallError> + 'static,
+    ) {
+        self.functions.insert(
+            name.into(),
+            Function {
+                arity,
+                body: Body::Calling(Box::new(body)),
+            },
+        );
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Function<T>> {
+        self.functions.get(name)
+    }
+}
+
+impl<T: Numeric> FunctionRegistry<T> {
+    // Creates a registry containing the built-in functions.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register("min", Arity::AtLeast(1), |args| {
+            Ok(extremum(args, Ordering::Less))
+        });
+
+        registry.register("max", Arity::AtLeast(1), |args| {
+            Ok(extremum(args, Ordering::Greater))
+        });
+
+        registry.register("abs", Arity::Exact(1), |args| {
+            args[0].checked_abs().ok_or(EvalErrorKind::Overflow)
+        });
+
+        registry.register("pow", Arity::Exact(2), |args| pow(&args[0], &args[1]));
+
+        registry.register("sqrt", Arity::Exact(1), |args| {
+            args[0].checked_sqrt().ok_or_else(|| EvalErrorKind::Custom {
+                message: format!("the square root of {} cannot be represented", args[0]),
+            })
+        });
+
+        registry.register("floor", Arity::Exact(1), |args| Ok(args[0].floor()));
+
+        registry.register_values("len", Arity::Exact(1), |args| {
+            let len = expect_list(&args[0])?.len();
+            count(len).map(Value::Number)
+        });
+
+        registry.register_values("sum", Arity::Exact(1), |args| {
+            sum(&expect_numbers(expect_list(&args[0])?)?).map(Value::Number)
+        });
+
+        registry.register_values("avg", Arity::Exact(1), |args| {
+            let numbers = expect_numbers(expect_list(&args[0])?)?;
+
+            if numbers.is_empty() {
+                return Err(EvalErrorKind::Custom {
+                    message: "cannot average an empty list".to_string(),
+                });
+            }
+
+            sum(&numbers)?
+                .checked_div(&count(numbers.len())?)
+                .map(Value::Number)
+                .ok_or(EvalErrorKind::Overflow)
+        });
+
+        // map(xs, f) applies f to each item of xs.
+        registry.register_calling("map", Arity::Exact(2), |args, call_arg| {
+            let items = expect_list(&args[0])?;
+            expect_function(&args[1])?;
+
+            items
+                .iter()
+                .map(|item| call_arg(1, vec![item.clone()]).map_err(CallError::Callee))
+                .collect::<Result<_, _>>()
+                .map(Value::List)
+        });
+
+        registry
+    }
+}
+
+impl<T: Numeric + 'static> FunctionRegistry<T> {
+    // A shared registry of the built-in functions, used when the caller doesn’t supply one. It is
+    // built once per thread and numeric type, since building it allocates every function.
+    pub(crate) fn builtins() -> Rc<Self> {
+        thread_local! {
+            static BUILTINS: RefCell<HashMap<TypeId, Rc<dyn Any>>> = RefCell::new(HashMap::new());
+        }
+
+        let registry = BUILTINS.with(|builtins| {
+            builtins
+                .borrow_mut()
+                .entry(TypeId::of::<T>())
+                .or_insert_with(|| Rc::new(Self::new()))
+                .clone()
+        });
+
+        // Entries are keyed by the type of the registry they hold.
+        registry.downcast().unwrap()
+    }
+}
+
+impl<T: Numeric> Default for FunctionRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Picks the argument furthest in the given direction, preferring earlier arguments on ties.
+fn extremum<T: PartialOrd + Clone>(args: &[T], direction: Ordering) -> T {
+    let mut result = &args[0];
+
+    for arg in &args[1..] {
+        if arg.partial_cmp(result) == Some(direction) {
+            result = arg;
+        }
+    }
+
+    result.clone()
+}
+
+fn expect_number<T>(value: &Value<T>) -> Result<&T, EvalErrorKind> {
+    match value {
+        Value::Number(n) => Ok(n),
+        other => Err(EvalErrorKind::TypeMismatch {
+            expected: ValueType::Number,
+            found: other.ty(),
+        }),
+    }
+}
+
+fn expect_numbers<T>(values: &[Value<T>]) -> Result<Vec<&T>, EvalErrorKind> {
+    values.iter().map(expect_number).collect()
+}
+
+fn expect_list<T>(value: &Value<T>) -> Result<&[Value<T>], EvalErrorKind> {
+    match value {
+        Value::List(items) => Ok(items),
+        other => Err(EvalErrorKind::TypeMismatch {
+            expected: ValueType::List,
+            found: other.ty(),
+        }),
+    }
+}
+
+fn expect_function<T>(value: &Value<T>) -> Result<(), EvalErrorKind> {
+    match value {
+        Value::Function(_) => Ok(()),
+        other => Err(EvalErrorKind::TypeMismatch {
+            expected: ValueType::Function,
+            found: other.ty(),
+        }),
+    }
+}
+
+fn count<T: Numeric>(len: usize) -> Result<T, EvalErrorKind> {
+    T::from_integer(len as u128).ok_or(EvalErrorKind::Overflow)
+}
+
+fn sum<T: Numeric>(numbers: &[&T]) -> Result<T, EvalErrorKind> {
+    numbers.iter().try_fold(count::<T>(0)?, |total, n| {
+        total.checked_add(n).ok_or(EvalErrorKind::Overflow)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn call(name: &str, args: &[i64]) -> Result<i64, EvalErrorKind> {
+        let args: Vec<_> = args.iter().copied().map(Value::Number).collect();
+
+        call_with_values(name, &args).map(|value| match value {
+            Value::Number(n) => n,
+            value => panic!("expected a number, found {:?}", value),
+        })
+    }
+
+    // Functions passed as arguments are never called, since closures can only come from
+    // evaluating an expression.
+    fn call_with_values(name: &str, args: &[Value<i64>]) -> Result<Value<i64>, EvalErrorKind> {
+        FunctionRegistry::new()
+            .get(name)
+            .unwrap()
+            .call(args, &mut |_, _| unreachable!())
+            .map_err(|error| match error {
+                CallError::Builtin(kind) => kind,
+                CallError::Callee(error) => panic!("unexpected error {:?}", error),
+            })
+    }
+
+    fn list(items: &[i64]) -> Value<i64> {
+        Value::List(items.iter().copied().map(Value::Number).collect())
+    }
+
+    #[test]
+    fn builtins_are_shared_within_a_thread() {
+        let first = FunctionRegistry::<i64>::builtins();
+        let second = FunctionRegistry::<i64>::builtins();
+
+        assert!(Rc::ptr_eq(&first, &second));
+        assert!(first.get("max").is_some());
+        assert!(FunctionRegistry::<f64>::builtins().get("max").is_some());
+    }
+
+    #[test]
+    fn map_is_only_a_builtin() {
+        assert!(FunctionRegistry::<i64>::new().get("map").is_some());
+        assert!(FunctionRegistry::<i64>::empty().get("map").is_none());
+    }
+
+    #[test]
+    fn map_checks_argument_types_before_calling() {
+        assert_eq!(
+            call_with_values("map", &[Value::Number(1), list(&[])]),
+            Err(EvalErrorKind::TypeMismatch {
+                expected: ValueType::List,
+                found: ValueType::Number,
+            }),
+        );
+        assert_eq!(
+            call_with_values("map", &[list(&[]), Value::Number(1)]),
+            Err(EvalErrorKind::TypeMismatch {
+                expected: ValueType::Function,
+                found: ValueType::Number,
+            }),
+        );
+    }
+
+    #[test]
+    fn arity_display_pluralizes() {
+        assert_eq!(Arity::Exact(1).to_string(), "1 argument");
+        assert_eq!(Arity::AtLeast(2).to_string(), "at least 2 arguments");
+    }
+
+    #[test]
+    fn min_and_max_accept_any_number_of_arguments() {
+        assert_eq!(call("min", &[3, -1, 2]), Ok(-1));
+        assert_eq!(call("max", &[3, -1, 2]), Ok(3));
+        assert_eq!(call("max", &[7]), Ok(7));
+    }
+
+    #[test]
+    fn pow_overflow_is_an_error() {
+        assert_eq!(call("pow", &[2, 10]), Ok(1024));
+        assert_eq!(call("pow", &[2, 64]), Err(EvalErrorKind::Overflow));
+    }
+
+    #[test]
+    fn pow_with_negative_exponent_is_an_error_for_integers() {
+        assert_eq!(call("pow", &[2, -1]), Err(EvalErrorKind::NegativeExponent));
+        assert_eq!(call("pow", &[1, 5_000_000_000]), Ok(1));
+    }
+
+    #[test]
+    fn square_root_of_negative_number_is_an_error() {
+        assert_eq!(
+            call("sqrt", &[-4]),
+            Err(EvalErrorKind::Custom {
+                message: "the square root of -4 cannot be represented".to_string(),
+            }),
+        );
+    }
+
+    #[test]
+    fn registered_functions_replace_builtins() {
+        let mut registry = FunctionRegistry::new();
+        registry.register("abs", Arity::Exact(1), |_| Ok(42_i64));
+
+        assert_eq!(
+            registry
+                .get("abs")
+                .unwrap()
+                .call(&[Value::Number(-1)], &mut |_, _| unreachable!()),
+            Ok(Value::Number(42)),
+        );
+    }
+
+    #[test]
+    fn aggregates_operate_on_lists() {
+        assert_eq!(
+            call_with_values("len", &[list(&[4, 5, 6])]),
+            Ok(Value::Number(3))
+        );
+        assert_eq!(
+            call_with_values("sum", &[list(&[4, 5, 6])]),
+            Ok(Value::Number(15))
+        );
+        assert_eq!(
+            call_with_values("avg", &[list(&[4, 5, 6])]),
+            Ok(Value::Number(5))
+        );
+        assert_eq!(call_with_values("sum", &[list(&[])]), Ok(Value::Number(0)));
+    }
+
+    #[test]
+    fn average_of_empty_list_is_an_error() {
+        assert_eq!(
+            call_with_values("avg", &[list(&[])]),
+            Err(EvalErrorKind::Custom {
+                message: "cannot average an empty list".to_string(),
+            }),
+        );
+    }
+
+    #[test]
+    fn aggregates_reject_non_lists_and_non_numbers() {
+        assert_eq!(
+            call_with_values("len", &[Value::Number(1)]),
+            Err(EvalErrorKind::TypeMismatch {
+                expected: ValueType::List,
+                found: ValueType::Number,
+            }),
+        );
+        assert_eq!(
+            call_with_values("sum", &[Value::List(vec![Value::Bool(true)])]),
+            Err(EvalErrorKind::TypeMismatch {
+                expected: ValueType::Number,
+                found: ValueType::Bool,
+            }),
+        );
+    }
+}
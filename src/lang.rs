@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
-pub(crate) enum Lang {}
+pub enum Lang {}
 
 impl rowan::Language for Lang {
     type Kind = crate::lexer::SyntaxKind;
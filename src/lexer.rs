@@ -1,5 +1,5 @@
 mod syntax_kind;
-pub(crate) use syntax_kind::SyntaxKind;
+pub use syntax_kind::SyntaxKind;
 
 use logos::Logos;
 use smol_str::SmolStr;
@@ -43,6 +43,34 @@ impl Iterator for Lexer<'_> {
     }
 }
 
+// Given the text following the `/*` that opens a block comment, returns the length up to and
+// including the matching `*/`, or None if the comment is never closed. Block comments nest, so
+// that commenting out code which already contains a block comment works as expected.
+pub(crate) fn block_comment_len(text: &str) -> Option<usize> {
+    let mut depth = 1;
+    let mut idx = 0;
+
+    while idx < text.len() {
+        let rest = &text[idx..];
+
+        if rest.starts_with("*/") {
+            depth -= 1;
+            idx += 2;
+
+            if depth == 0 {
+                return Some(idx);
+            }
+        } else if rest.starts_with("/*") {
+            depth += 1;
+            idx += 2;
+        } else {
+            idx += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,4 +130,37 @@ mod tests {
 
         assert_eq!(lexer.next(), None);
     }
+
+    #[test]
+    fn block_comment_len_includes_closing_delimiter() {
+        assert_eq!(block_comment_len(" a */ 1"), Some(5));
+    }
+
+    #[test]
+    fn block_comment_len_skips_nested_comments() {
+        assert_eq!(block_comment_len(" /* */ */"), Some(9));
+        assert_eq!(block_comment_len(" /* */"), None);
+    }
+
+    #[test]
+    fn mixed_line_endings_have_correct_ranges() {
+        let lexemes: Vec<_> = Lexer::new("1\r\n+\n\t2\u{A0}\r*\u{2028}3")
+            .map(|lexeme| (lexeme.kind, lexeme.range))
+            .collect();
+
+        assert_eq!(
+            lexemes,
+            [
+                (SyntaxKind::Number, range(0, 1)),
+                (SyntaxKind::Whitespace, range(1, 3)),
+                (SyntaxKind::Plus, range(3, 4)),
+                (SyntaxKind::Whitespace, range(4, 6)),
+                (SyntaxKind::Number, range(6, 7)),
+                (SyntaxKind::Whitespace, range(7, 10)),
+                (SyntaxKind::Star, range(10, 11)),
+                (SyntaxKind::Whitespace, range(11, 14)),
+                (SyntaxKind::Number, range(14, 15)),
+            ],
+        );
+    }
 }
@@ -4,13 +4,32 @@ use std::fmt;
 
 #[derive(Logos, Debug, Copy, Clone, PartialEq, IntoPrimitive, TryFromPrimitive)]
 #[repr(u16)]
-pub(crate) enum SyntaxKind {
-    #[regex("[ \n]+")]
+pub enum SyntaxKind {
+    // Any Unicode whitespace, so that tabs, Windows line endings and non-breaking spaces pasted in
+    // from elsewhere are all accepted.
+    #[regex(r"\p{White_Space}+")]
     Whitespace,
 
-    #[regex("[1234567890]+")]
+    // The line ending isn’t part of the comment, whether it’s `\n` or `\r\n`.
+    #[regex("//[^\r\n]*")]
+    LineComment,
+
+    #[token("/*", block_comment)]
+    BlockComment,
+
+    #[regex(r"[0-9][0-9_]*(\.[0-9_]*)?([eE][+-]?[0-9_]*)?")]
+    #[regex(r"\.[0-9][0-9_]*([eE][+-]?[0-9_]*)?")]
+    #[regex("0[xob][0-9a-zA-Z_]*")]
     Number,
 
+    // Unterminated strings and invalid escapes are lexed as strings too, and reported by the
+    // parser.
+    #[regex(r#""([^"\\]|\\(.|\n))*\\?"?"#)]
+    String,
+
+    #[regex("[A-Za-z_][A-Za-z0-9_]*")]
+    Ident,
+
     #[token("+")]
     Plus,
 
@@ -23,11 +42,122 @@ pub(crate) enum SyntaxKind {
     #[token("/")]
     Slash,
 
+    #[token("div")]
+    DivKw,
+
+    #[token("true")]
+    TrueKw,
+
+    #[token("false")]
+    FalseKw,
+
+    #[token("if")]
+    IfKw,
+
+    #[token("then")]
+    ThenKw,
+
+    #[token("else")]
+    ElseKw,
+
+    #[token("let")]
+    LetKw,
+
+    #[token("fn")]
+    FnKw,
+
+    #[token("%")]
+    Percent,
+
+    #[token("^")]
+    Caret,
+
+    #[token("==")]
+    EqEq,
+
+    #[token("!=")]
+    BangEq,
+
+    #[token("<")]
+    Less,
+
+    #[token("<=")]
+    LessEq,
+
+    #[token(">")]
+    Greater,
+
+    #[token(">=")]
+    GreaterEq,
+
+    #[token("&&")]
+    AmpAmp,
+
+    #[token("||")]
+    PipePipe,
+
+    #[token("!")]
+    Bang,
+
+    #[token("(")]
+    LParen,
+
+    #[token(")")]
+    RParen,
+
+    #[token("[")]
+    LBracket,
+
+    #[token("]")]
+    RBracket,
+
+    #[token(",")]
+    Comma,
+
+    #[token("=")]
+    Equals,
+
+    #[token(";")]
+    Semicolon,
+
     #[error]
     Error,
 
     Root,
+    NameRef,
     Operation,
+    ParenExpr,
+    PrefixExpr,
+    CallExpr,
+    ArgList,
+    ConditionalExpr,
+    LetStmt,
+    ExprStmt,
+    Lambda,
+    ParamList,
+    ListExpr,
+    IndexExpr,
+    // A zero-width placeholder for an operand that should have been there.
+    Missing,
+    // A run of lexemes that didn’t fit where they were found.
+    ErrorNode,
+}
+
+impl SyntaxKind {
+    // Trivia can appear between any two tokens, and is ignored by the parser other than being
+    // kept in the tree.
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            Self::Whitespace | Self::LineComment | Self::BlockComment
+        )
+    }
+}
+
+// An unterminated block comment extends to the end of the input, and is reported by the parser.
+fn block_comment(lexer: &mut logos::Lexer<'_, SyntaxKind>) {
+    let remainder = lexer.remainder();
+    lexer.bump(super::block_comment_len(remainder).unwrap_or(remainder.len()));
 }
 
 impl From<SyntaxKind> for rowan::SyntaxKind {
@@ -40,13 +170,57 @@ impl fmt::Display for SyntaxKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
             Self::Whitespace => "whitespace",
+            Self::LineComment | Self::BlockComment => "a comment",
             Self::Number => "a number literal",
+            Self::String => "a string literal",
+            Self::Ident => "an identifier",
             Self::Plus => "a plus sign",
             Self::Minus => "a minus sign",
             Self::Star => "an asterisk",
             Self::Slash => "a slash",
+            Self::DivKw => "`div`",
+            Self::TrueKw => "`true`",
+            Self::FalseKw => "`false`",
+            Self::IfKw => "`if`",
+            Self::ThenKw => "`then`",
+            Self::ElseKw => "`else`",
+            Self::LetKw => "`let`",
+            Self::FnKw => "`fn`",
+            Self::Percent => "a percent sign",
+            Self::Caret => "a caret",
+            Self::EqEq => "a double equals sign",
+            Self::BangEq => "a not-equals sign",
+            Self::Less => "a less-than sign",
+            Self::LessEq => "a less-than-or-equals sign",
+            Self::Greater => "a greater-than sign",
+            Self::GreaterEq => "a greater-than-or-equals sign",
+            Self::AmpAmp => "a double ampersand",
+            Self::PipePipe => "a double vertical bar",
+            Self::Bang => "an exclamation mark",
+            Self::LParen => "a left parenthesis",
+            Self::RParen => "a right parenthesis",
+            Self::LBracket => "a left bracket",
+            Self::RBracket => "a right bracket",
+            Self::Comma => "a comma",
+            Self::Equals => "an equals sign",
+            Self::Semicolon => "a semicolon",
             Self::Error => "an erroneous character",
-            _ => unreachable!(),
+            Self::Root => "a program",
+            Self::NameRef => "a name",
+            Self::Operation => "a binary operation",
+            Self::ParenExpr => "a parenthesized expression",
+            Self::PrefixExpr => "a prefix operation",
+            Self::CallExpr => "a function call",
+            Self::ArgList => "an argument list",
+            Self::ConditionalExpr => "a conditional expression",
+            Self::LetStmt => "a let statement",
+            Self::ExprStmt => "an expression statement",
+            Self::Lambda => "an anonymous function",
+            Self::ParamList => "a parameter list",
+            Self::ListExpr => "a list",
+            Self::IndexExpr => "an indexing expression",
+            Self::Missing => "a missing expression",
+            Self::ErrorNode => "unexpected input",
         })
     }
 }
@@ -55,6 +229,12 @@ impl fmt::Display for SyntaxKind {
 mod tests {
     use super::*;
 
+    #[test]
+    fn node_kinds_have_descriptions() {
+        assert_eq!(SyntaxKind::Root.to_string(), "a program");
+        assert_eq!(SyntaxKind::ErrorNode.to_string(), "unexpected input");
+    }
+
     #[test]
     fn lexes_nothing() {
         assert_eq!(SyntaxKind::lexer("").next(), None);
@@ -77,11 +257,142 @@ mod tests {
         test("\n\n", SyntaxKind::Whitespace);
     }
 
+    #[test]
+    fn lexes_tabs() {
+        test("\t \t", SyntaxKind::Whitespace);
+    }
+
+    #[test]
+    fn lexes_windows_line_endings() {
+        test("\r\n\r\n", SyntaxKind::Whitespace);
+    }
+
+    #[test]
+    fn lexes_unicode_whitespace() {
+        test("\u{A0}\u{2003}\u{3000}\u{2028}", SyntaxKind::Whitespace);
+    }
+
+    #[test]
+    fn line_comments_end_before_windows_line_endings() {
+        let mut lexer = SyntaxKind::lexer("// note\r\n1");
+
+        assert_eq!(lexer.next(), Some(SyntaxKind::LineComment));
+        assert_eq!(lexer.slice(), "// note");
+        assert_eq!(lexer.next(), Some(SyntaxKind::Whitespace));
+        assert_eq!(lexer.slice(), "\r\n");
+    }
+
     #[test]
     fn lexes_numbers() {
         test("1234567890", SyntaxKind::Number);
     }
 
+    #[test]
+    fn lexes_decimals() {
+        test("3.14", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_decimals_without_integer_part() {
+        test(".5", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_exponents() {
+        test("6.02e-23", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_digit_separators() {
+        test("1_000_000", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_hexadecimal() {
+        test("0xDEAD_beef", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_octal() {
+        test("0o17", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_binary() {
+        test("0b1010", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_invalid_digits_as_part_of_radix_literal() {
+        test("0b102", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_malformed_decimals() {
+        test("1.", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_malformed_exponents() {
+        test("1e", SyntaxKind::Number);
+    }
+
+    #[test]
+    fn lexes_strings() {
+        test(r#""Total: ""#, SyntaxKind::String);
+    }
+
+    #[test]
+    fn lexes_strings_with_escaped_quotes() {
+        test(r#""say \"hi\"\n""#, SyntaxKind::String);
+    }
+
+    #[test]
+    fn lexes_unterminated_strings() {
+        test("\"abc", SyntaxKind::String);
+        test("\"abc\\", SyntaxKind::String);
+        test("\"abc\\\"", SyntaxKind::String);
+    }
+
+    #[test]
+    fn lexes_strings_spanning_lines() {
+        test("\"a\nb\"", SyntaxKind::String);
+    }
+
+    #[test]
+    fn lexes_line_comments() {
+        test("// note", SyntaxKind::LineComment);
+    }
+
+    #[test]
+    fn line_comments_end_before_newline() {
+        let mut lexer = SyntaxKind::lexer("// note\n1");
+
+        assert_eq!(lexer.next(), Some(SyntaxKind::LineComment));
+        assert_eq!(lexer.slice(), "// note");
+        assert_eq!(lexer.next(), Some(SyntaxKind::Whitespace));
+    }
+
+    #[test]
+    fn lexes_block_comments() {
+        test("/* a\nb */", SyntaxKind::BlockComment);
+    }
+
+    #[test]
+    fn lexes_nested_block_comments() {
+        test("/* a /* b */ c */", SyntaxKind::BlockComment);
+    }
+
+    #[test]
+    fn lexes_unterminated_block_comments() {
+        test("/* a /* b */", SyntaxKind::BlockComment);
+    }
+
+    #[test]
+    fn lexes_identifiers() {
+        test("_total2", SyntaxKind::Ident);
+    }
+
     #[test]
     fn lexes_plus() {
         test("+", SyntaxKind::Plus);
@@ -101,4 +412,127 @@ mod tests {
     fn lexes_slash() {
         test("/", SyntaxKind::Slash);
     }
+
+    #[test]
+    fn lexes_div_keyword() {
+        test("div", SyntaxKind::DivKw);
+    }
+
+    #[test]
+    fn lexes_identifiers_starting_with_keywords() {
+        test("divisor", SyntaxKind::Ident);
+    }
+
+    #[test]
+    fn lexes_boolean_keywords() {
+        test("true", SyntaxKind::TrueKw);
+        test("false", SyntaxKind::FalseKw);
+    }
+
+    #[test]
+    fn lexes_conditional_keywords() {
+        test("if", SyntaxKind::IfKw);
+        test("then", SyntaxKind::ThenKw);
+        test("else", SyntaxKind::ElseKw);
+    }
+
+    #[test]
+    fn lexes_let_keyword() {
+        test("let", SyntaxKind::LetKw);
+    }
+
+    #[test]
+    fn lexes_fn_keyword() {
+        test("fn", SyntaxKind::FnKw);
+    }
+
+    #[test]
+    fn lexes_percent() {
+        test("%", SyntaxKind::Percent);
+    }
+
+    #[test]
+    fn lexes_caret() {
+        test("^", SyntaxKind::Caret);
+    }
+
+    #[test]
+    fn lexes_double_equals() {
+        test("==", SyntaxKind::EqEq);
+    }
+
+    #[test]
+    fn lexes_bang_equals() {
+        test("!=", SyntaxKind::BangEq);
+    }
+
+    #[test]
+    fn lexes_less() {
+        test("<", SyntaxKind::Less);
+    }
+
+    #[test]
+    fn lexes_less_or_equal() {
+        test("<=", SyntaxKind::LessEq);
+    }
+
+    #[test]
+    fn lexes_greater() {
+        test(">", SyntaxKind::Greater);
+    }
+
+    #[test]
+    fn lexes_greater_or_equal() {
+        test(">=", SyntaxKind::GreaterEq);
+    }
+
+    #[test]
+    fn lexes_double_ampersand() {
+        test("&&", SyntaxKind::AmpAmp);
+    }
+
+    #[test]
+    fn lexes_double_pipe() {
+        test("||", SyntaxKind::PipePipe);
+    }
+
+    #[test]
+    fn lexes_bang() {
+        test("!", SyntaxKind::Bang);
+    }
+
+    #[test]
+    fn lexes_left_parenthesis() {
+        test("(", SyntaxKind::LParen);
+    }
+
+    #[test]
+    fn lexes_right_parenthesis() {
+        test(")", SyntaxKind::RParen);
+    }
+
+    #[test]
+    fn lexes_left_bracket() {
+        test("[", SyntaxKind::LBracket);
+    }
+
+    #[test]
+    fn lexes_right_bracket() {
+        test("]", SyntaxKind::RBracket);
+    }
+
+    #[test]
+    fn lexes_comma() {
+        test(",", SyntaxKind::Comma);
+    }
+
+    #[test]
+    fn lexes_equals() {
+        test("=", SyntaxKind::Equals);
+    }
+
+    #[test]
+    fn lexes_semicolon() {
+        test(";", SyntaxKind::Semicolon);
+    }
 }
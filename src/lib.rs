@@ -1,18 +1,44 @@
-mod ast;
+pub mod ast;
+mod env;
 mod errors;
+mod functions;
 mod lang;
 mod lexer;
+mod literal;
+mod numeric;
 mod parser;
+mod value;
 
-type SyntaxNode = rowan::SyntaxNode<lang::Lang>;
-type SyntaxToken = rowan::SyntaxToken<lang::Lang>;
-type SyntaxElement = rowan::NodeOrToken<SyntaxNode, SyntaxToken>;
+pub type SyntaxNode = rowan::SyntaxNode<Lang>;
+pub type SyntaxToken = rowan::SyntaxToken<Lang>;
+pub type SyntaxElement = rowan::NodeOrToken<SyntaxNode, SyntaxToken>;
 
-enum Op {
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Op {
     Add,
     Mul,
     Div,
     Sub,
+    DivEuclid,
+    RemEuclid,
+    Pow,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Not,
 }
 
+pub use env::{EmptyEnvironment, Environment};
+pub use errors::{EvalError, EvalErrorKind, Radix, Suggestion, SyntaxError, SyntaxErrorKind};
+pub use functions::{Arity, FunctionRegistry};
+pub use lang::Lang;
+pub use lexer::SyntaxKind;
+pub use literal::Literal;
+pub use numeric::{Numeric, Rational};
 pub use parser::{Parse, Parser};
+pub use value::{Closure, Value, ValueType};
@@ -0,0 +1,408 @@
+use crate::errors::{Radix, SyntaxErrorKind};
+use std::convert::TryFrom;
+use std::ops::Range;
+
+// The value of a number literal, equal to significand × 10^exponent. This representation is
+// exact, so it’s up to each Numeric implementation to decide how much precision to keep.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Literal {
+    pub significand: u128,
+    pub exponent: i32,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum LiteralError {
+    // The literal isn’t well-formed; the range is relative to the start of the literal.
+    Malformed {
+        kind: SyntaxErrorKind,
+        range: Range<usize>,
+    },
+    // The literal is well-formed, but too large to store as a Literal.
+    OutOfRange,
+}
+
+pub(crate) fn parse(text: &str) -> Result<Literal, LiteralError> {
+    let radix = match text.get(..2) {
+        Some("0x") => Radix::Hexadecimal,
+        Some("0o") => Radix::Octal,
+        Some("0b") => Radix::Binary,
+        _ => return parse_decimal(text),
+    };
+
+    let digits = &text[2..];
+
+    if !digits.chars().any(|c| c != '_') {
+        return Err(LiteralError::Malformed {
+            kind: SyntaxErrorKind::MissingRadixDigits,
+            range: 0..text.len(),
+        });
+    }
+
+    Ok(Literal {
+        significand: parse_digits(digits, 2, radix)?,
+        exponent: 0,
+    })
+}
+
+fn parse_decimal(text: &str) -> Result<Literal, LiteralError> {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(idx) => (&text[..idx], Some((idx, &text[idx + 1..]))),
+        None => (text, None),
+    };
+
+    let (integer_digits, fraction_digits) = match mantissa.find('.') {
+        Some(idx) => {
+            let fraction_digits = &mantissa[idx + 1..];
+
+            if count_digits(fraction_digits) == 0 {
+                return Err(LiteralError::Malformed {
+                    kind: SyntaxErrorKind::MissingFractionDigits,
+                    range: idx..idx + 1,
+                });
+            }
+
+            (&mantissa[..idx], fraction_digits)
+        }
+        None => (mantissa, ""),
+    };
+
+    let exponent = match exponent {
+        Some((idx, exponent)) => {
+            let digits = exponent.trim_start_matches(['+', '-']);
+
+            if count_digits(digits) == 0 {
+                return Err(LiteralError::Malformed {
+                    kind: SyntaxErrorKind::MissingExponentDigits,
+                    range: idx..text.len(),
+                });
+            }
+
+            // An exponent that is out of range is only an error if the significand isn’t zero,
+            // which isn’t known yet.
+            let magnitude = match parse_digits(digits, text.len() - digits.len(), Radix::Decimal) {
+                Ok(magnitude) => i32::try_from(magnitude).ok(),
+                Err(LiteralError::OutOfRange) => None,
+                Err(error) => return Err(error),
+            };
+
+            if exponent.starts_with('-') {
+                magnitude.map(|magnitude| -magnitude)
+            } else {
+                magnitude
+            }
+        }
+        None => Some(0),
+    };
+
+    let num_fraction_digits = count_digits(fraction_digits);
+
+    let integer_part = parse_digits(integer_digits, 0, Radix::Decimal)?;
+    let fraction_part = parse_digits(fraction_digits, integer_digits.len() + 1, Radix::Decimal)?;
+
+    let significand = u32::try_from(num_fraction_digits)
+        .ok()
+        .and_then(|num_fraction_digits| 10_u128.checked_pow(num_fraction_digits))
+        .and_then(|scale| integer_part.checked_mul(scale))
+        .and_then(|integer_part| integer_part.checked_add(fraction_part))
+        .ok_or(LiteralError::OutOfRange)?;
+
+    // Zero is zero whatever its exponent, so it’s normalized to keep numeric types from rejecting
+    // it for having an exponent they can’t scale by.
+    if significand == 0 {
+        return Ok(Literal {
+            significand: 0,
+            exponent: 0,
+        });
+    }
+
+    let exponent = exponent.ok_or(LiteralError::OutOfRange)?;
+    let exponent = i32::try_from(num_fraction_digits)
+        .ok()
+        .and_then(|num_fraction_digits| i32::checked_sub(exponent, num_fraction_digits))
+        .ok_or(LiteralError::OutOfRange)?;
+
+    Ok(Literal {
+        significand,
+        exponent,
+    })
+}
+
+// Underscores can be used to separate digits, but don’t count as digits themselves.
+fn count_digits(digits: &str) -> usize {
+    digits.chars().filter(|c| *c != '_').count()
+}
+
+// Parses digits in the given radix, skipping separators. The offset is the position of the digits
+// within the literal, and is used for reporting invalid digits.
+fn parse_digits(digits: &str, offset: usize, radix: Radix) -> Result<u128, LiteralError> {
+    // Keep scanning after overflowing, since invalid digits take priority over the value being
+    // out of range.
+    let mut value = Some(0_u128);
+
+    for (idx, c) in digits.char_indices().filter(|(_, c)| *c != '_') {
+        let digit = c
+            .to_digit(radix.value())
+            .ok_or_else(|| LiteralError::Malformed {
+                kind: SyntaxErrorKind::InvalidDigit { radix },
+                range: offset + idx..offset + idx + c.len_utf8(),
+            })?;
+
+        value = value
+            .and_then(|value| value.checked_mul(u128::from(radix.value())))
+            .and_then(|value| value.checked_add(u128::from(digit)));
+    }
+
+    value.ok_or(LiteralError::OutOfRange)
+}
+
+// Decodes the escape sequences in a string literal, including its quotes.
+pub(crate) fn parse_string(text: &str) -> Result<String, LiteralError> {
+    let mut value = String::new();
+
+    // Skip the opening quote.
+    let mut chars = text.char_indices().skip(1);
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            // The lexer ends strings at the first unescaped quote.
+            '"' => return Ok(value),
+            '\\' => value.push(parse_escape(text, idx, &mut chars)?),
+            c => value.push(c),
+        }
+    }
+
+    Err(LiteralError::Malformed {
+        kind: SyntaxErrorKind::UnterminatedString,
+        range: 0..1,
+    })
+}
+
+// Parses the escape sequence starting with the backslash at start, consuming it from chars.
+fn parse_escape(
+    text: &str,
+    start: usize,
+    chars: &mut impl Iterator<Item = (usize, char)>,
+) -> Result<char, LiteralError> {
+    let malformed = |kind, end| LiteralError::Malformed {
+        kind,
+        range: start..end,
+    };
+
+    let (idx, c) = chars.next().ok_or(LiteralError::Malformed {
+        kind: SyntaxErrorKind::UnterminatedString,
+        range: 0..1,
+    })?;
+
+    Ok(match c {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        '0' => '\0',
+        '\\' => '\\',
+        '"' => '"',
+        'u' => {
+            let rest = &text[idx + 1..];
+
+            let digits = match rest.strip_prefix('{') {
+                Some(rest) => {
+                    &rest[..rest
+                        .find(|c: char| !c.is_ascii_hexdigit())
+                        .unwrap_or(rest.len())]
+                }
+                None => return Err(malformed(SyntaxErrorKind::InvalidUnicodeEscape, idx + 1)),
+            };
+
+            let is_closed = rest[1 + digits.len()..].starts_with('}');
+
+            // Everything up to the closing brace is ASCII, so it’s as many chars as it is bytes.
+            let len = 1 + digits.len() + usize::from(is_closed);
+            chars.take(len).for_each(drop);
+
+            let end = idx + 1 + len;
+
+            if !is_closed || digits.is_empty() || digits.len() > 6 {
+                return Err(malformed(SyntaxErrorKind::InvalidUnicodeEscape, end));
+            }
+
+            u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| malformed(SyntaxErrorKind::InvalidUnicodeEscape, end))?
+        }
+        c => {
+            return Err(malformed(
+                SyntaxErrorKind::InvalidEscape,
+                idx + c.len_utf8(),
+            ))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn check(input: &str, significand: u128, exponent: i32) {
+        assert_eq!(
+            parse(input),
+            Ok(Literal {
+                significand,
+                exponent,
+            }),
+        );
+    }
+
+    fn check_malformed(input: &str, kind: SyntaxErrorKind, range: Range<usize>) {
+        assert_eq!(parse(input), Err(LiteralError::Malformed { kind, range }));
+    }
+
+    #[test]
+    fn parse_integer() {
+        check("1234", 1234, 0);
+    }
+
+    #[test]
+    fn parse_decimal() {
+        check("3.14", 314, -2);
+    }
+
+    #[test]
+    fn parse_decimal_without_integer_part() {
+        check(".5", 5, -1);
+    }
+
+    #[test]
+    fn parse_exponent() {
+        check("1e9", 1, 9);
+    }
+
+    #[test]
+    fn parse_decimal_with_negative_exponent() {
+        check("6.02E-23", 602, -25);
+    }
+
+    #[test]
+    fn parse_digit_separators() {
+        check("1_000.000_1e1_0", 10000001, 6);
+    }
+
+    #[test]
+    fn zero_has_no_exponent() {
+        check("0e-50", 0, 0);
+        check("0.000", 0, 0);
+        check("0e99999999999", 0, 0);
+    }
+
+    #[test]
+    fn parse_hexadecimal() {
+        check("0xFf_ff", 0xffff, 0);
+    }
+
+    #[test]
+    fn parse_octal() {
+        check("0o17", 0o17, 0);
+    }
+
+    #[test]
+    fn parse_binary() {
+        check("0b1010", 0b1010, 0);
+    }
+
+    #[test]
+    fn invalid_digit_for_radix_is_malformed() {
+        check_malformed(
+            "0b1_02",
+            SyntaxErrorKind::InvalidDigit {
+                radix: Radix::Binary,
+            },
+            5..6,
+        );
+    }
+
+    #[test]
+    fn radix_prefix_without_digits_is_malformed() {
+        check_malformed("0x_", SyntaxErrorKind::MissingRadixDigits, 0..3);
+    }
+
+    #[test]
+    fn invalid_digit_is_reported_even_if_out_of_range() {
+        check_malformed(
+            "0xffffffffffffffffffffffffffffffffffg",
+            SyntaxErrorKind::InvalidDigit {
+                radix: Radix::Hexadecimal,
+            },
+            36..37,
+        );
+    }
+
+    #[test]
+    fn decimal_point_without_digits_is_malformed() {
+        check_malformed("1.", SyntaxErrorKind::MissingFractionDigits, 1..2);
+    }
+
+    #[test]
+    fn exponent_without_digits_is_malformed() {
+        check_malformed("1e+", SyntaxErrorKind::MissingExponentDigits, 1..3);
+    }
+
+    #[test]
+    fn huge_literal_is_out_of_range() {
+        assert_eq!(
+            parse("999999999999999999999999999999999999999999"),
+            Err(LiteralError::OutOfRange),
+        );
+    }
+
+    fn check_string(input: &str, expected: &str) {
+        assert_eq!(parse_string(input), Ok(expected.to_string()));
+    }
+
+    fn check_malformed_string(input: &str, kind: SyntaxErrorKind, range: Range<usize>) {
+        assert_eq!(
+            parse_string(input),
+            Err(LiteralError::Malformed { kind, range })
+        );
+    }
+
+    #[test]
+    fn parse_plain_string() {
+        check_string(r#""Total: ""#, "Total: ");
+    }
+
+    #[test]
+    fn parse_string_escapes() {
+        check_string(r#""a\nb\tc\r\0\\\"""#, "a\nb\tc\r\0\\\"");
+    }
+
+    #[test]
+    fn parse_unicode_escapes() {
+        check_string(r#""\u{48}\u{1F600}!""#, "H\u{1F600}!");
+    }
+
+    #[test]
+    fn unterminated_string_is_reported_at_opening_quote() {
+        check_malformed_string(r#""abc"#, SyntaxErrorKind::UnterminatedString, 0..1);
+        check_malformed_string(r#""abc\""#, SyntaxErrorKind::UnterminatedString, 0..1);
+        check_malformed_string(r#""abc\"#, SyntaxErrorKind::UnterminatedString, 0..1);
+    }
+
+    #[test]
+    fn unknown_escape_is_reported() {
+        check_malformed_string(r#""a\qb""#, SyntaxErrorKind::InvalidEscape, 2..4);
+        check_malformed_string(r#""\é""#, SyntaxErrorKind::InvalidEscape, 1..4);
+    }
+
+    #[test]
+    fn malformed_unicode_escape_is_reported() {
+        check_malformed_string(r#""\u41""#, SyntaxErrorKind::InvalidUnicodeEscape, 1..3);
+        check_malformed_string(r#""\u{}""#, SyntaxErrorKind::InvalidUnicodeEscape, 1..5);
+        check_malformed_string(r#""\u{41""#, SyntaxErrorKind::InvalidUnicodeEscape, 1..6);
+        check_malformed_string(
+            r#""\u{1234567}""#,
+            SyntaxErrorKind::InvalidUnicodeEscape,
+            1..12,
+        );
+        check_malformed_string(r#""\u{D800}""#, SyntaxErrorKind::InvalidUnicodeEscape, 1..9);
+    }
+}
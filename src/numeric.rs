@@ -0,0 +1,637 @@
+use crate::literal::Literal;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+pub trait Numeric: Sized + Clone + PartialOrd + fmt::Debug + fmt::Display {
+    // Returns None if the literal can’t be represented.
+    fn from_integer(value: u128) -> Option<Self>;
+
+    // By default only literals with integral values are accepted.
+    fn from_literal(literal: Literal) -> Option<Self> {
+        let Literal {
+            significand,
+            exponent,
+        } = literal;
+
+        let scale = 10_u128.checked_pow(exponent.unsigned_abs())?;
+
+        let value = if exponent >= 0 {
+            significand.checked_mul(scale)?
+        } else if significand % scale == 0 {
+            significand / scale
+        } else {
+            return None;
+        };
+
+        Self::from_integer(value)
+    }
+
+    fn is_zero(&self) -> bool;
+
+    // Whether checked_pow can be called with a negative exponent. Where it can’t, negative
+    // exponents are reported separately.
+    const NEGATIVE_EXPONENTS: bool = true;
+
+    // Each of these returns None if the result can’t be represented. The division methods are
+    // never called with a divisor of zero, since that is reported separately.
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+    fn checked_div(&self, rhs: &Self) -> Option<Self>;
+    fn checked_div_euclid(&self, rhs: &Self) -> Option<Self>;
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self>;
+    fn checked_neg(&self) -> Option<Self>;
+    fn checked_pow(&self, exponent: &Self) -> Option<Self>;
+    fn checked_sqrt(&self) -> Option<Self>;
+
+    fn checked_abs(&self) -> Option<Self> {
+        if *self < Self::from_integer(0)? {
+            self.checked_neg()
+        } else {
+            Some(self.clone())
+        }
+    }
+
+    fn floor(&self) -> Self;
+
+    // Returns None unless the value is a whole number that fits, e.g. when used as a list index.
+    fn to_integer(&self) -> Option<u128>;
+}
+
+macro_rules! impl_numeric_for_int {
+    ($ty:ty) => {
+        impl Numeric for $ty {
+            // The reciprocal of an integer is rarely an integer.
+            const NEGATIVE_EXPONENTS: bool = false;
+
+            fn from_integer(value: u128) -> Option<Self> {
+                Self::try_from(value).ok()
+            }
+
+            fn is_zero(&self) -> bool {
+                *self == 0
+            }
+
+            fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                <$ty>::checked_add(*self, *rhs)
+            }
+
+            fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                <$ty>::checked_sub(*self, *rhs)
+            }
+
+            fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+                <$ty>::checked_mul(*self, *rhs)
+            }
+
+            fn checked_div(&self, rhs: &Self) -> Option<Self> {
+                <$ty>::checked_div(*self, *rhs)
+            }
+
+            fn checked_div_euclid(&self, rhs: &Self) -> Option<Self> {
+                <$ty>::checked_div_euclid(*self, *rhs)
+            }
+
+            fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self> {
+                <$ty>::checked_rem_euclid(*self, *rhs)
+            }
+
+            fn checked_neg(&self) -> Option<Self> {
+                <$ty>::checked_neg(*self)
+            }
+
+            fn checked_pow(&self, exponent: &Self) -> Option<Self> {
+                let exponent = match u32::try_from(*exponent) {
+                    Ok(exponent) => exponent,
+                    // Powers of 0, 1 and -1 never grow, so all that matters about a huge
+                    // exponent is whether it’s even.
+                    Err(_) if *exponent > 0 && matches!(i128::from(*self), -1..=1) => {
+                        2 + (*exponent % 2) as u32
+                    }
+                    Err(_) => return None,
+                };
+
+                <$ty>::checked_pow(*self, exponent)
+            }
+
+            // Like division, square roots of integers are rounded down.
+            fn checked_sqrt(&self) -> Option<Self> {
+                Self::try_from(isqrt(u128::try_from(*self).ok()?)).ok()
+            }
+
+            fn floor(&self) -> Self {
+                *self
+            }
+
+            fn to_integer(&self) -> Option<u128> {
+                u128::try_from(*self).ok()
+            }
+        }
+    };
+}
+
+impl_numeric_for_int!(i64);
+impl_numeric_for_int!(u64);
+
+// Floating-point arithmetic saturates to infinity rather than wrapping, so we treat any
+// non-finite result as an overflow.
+fn finite(value: f64) -> Option<f64> {
+    if value.is_finite() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+impl Numeric for f64 {
+    fn from_integer(value: u128) -> Option<Self> {
+        Some(value as f64)
+    }
+
+    fn from_literal(literal: Literal) -> Option<Self> {
+        // Going through the standard library’s parser gives us correct rounding.
+        let value: f64 = format!("{}e{}", literal.significand, literal.exponent)
+            .parse()
+            .ok()?;
+
+        finite(value)
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0.0
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        finite(self + rhs)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        finite(self - rhs)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        finite(self * rhs)
+    }
+
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        finite(self / rhs)
+    }
+
+    fn checked_div_euclid(&self, rhs: &Self) -> Option<Self> {
+        finite(self.div_euclid(*rhs))
+    }
+
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self> {
+        finite(self.rem_euclid(*rhs))
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(-self)
+    }
+
+    fn checked_pow(&self, exponent: &Self) -> Option<Self> {
+        finite(self.powf(*exponent))
+    }
+
+    fn checked_sqrt(&self) -> Option<Self> {
+        finite(self.sqrt())
+    }
+
+    fn floor(&self) -> Self {
+        f64::floor(*self)
+    }
+
+    fn to_integer(&self) -> Option<u128> {
+        if *self >= 0.0 && self.fract() == 0.0 && *self < 2_f64.powi(128) {
+            Some(*self as u128)
+        } else {
+            None
+        }
+    }
+}
+
+// An exact fraction, always kept in lowest terms with a positive denominator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i64,
+    denom: i64,
+}
+
+impl Rational {
+    // Returns None if the denominator is zero or the fraction can’t be represented once reduced.
+    pub fn new(numer: i64, denom: i64) -> Option<Self> {
+        Self::reduce(i128::from(numer), i128::from(denom))
+    }
+
+    pub fn numer(self) -> i64 {
+        self.numer
+    }
+
+    pub fn denom(self) -> i64 {
+        self.denom
+    }
+
+    fn reduce(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+
+        let gcd = gcd(numer, denom);
+        let sign = if denom < 0 { -1 } else { 1 };
+
+        Some(Self {
+            numer: i64::try_from(numer / gcd * sign).ok()?,
+            denom: i64::try_from(denom / gcd * sign).ok()?,
+        })
+    }
+
+    fn parts(self) -> (i128, i128) {
+        (i128::from(self.numer), i128::from(self.denom))
+    }
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+
+    a.abs()
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Both denominators are positive, so cross-multiplying preserves the ordering.
+        let (a, b) = self.parts();
+        let (c, d) = other.parts();
+
+        (a * d).cmp(&(c * b))
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Self {
+            numer: value,
+            denom: 1,
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denom == 1 {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+impl Numeric for Rational {
+    fn from_integer(value: u128) -> Option<Self> {
+        i64::try_from(value).ok().map(Self::from)
+    }
+
+    fn from_literal(literal: Literal) -> Option<Self> {
+        let significand = i128::try_from(literal.significand).ok()?;
+        let scale = 10_i128.checked_pow(literal.exponent.unsigned_abs())?;
+
+        if literal.exponent >= 0 {
+            Self::reduce(significand.checked_mul(scale)?, 1)
+        } else {
+            Self::reduce(significand, scale)
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.numer == 0
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        let (a, b) = self.parts();
+        let (c, d) = rhs.parts();
+
+        Self::reduce(a * d + c * b, b * d)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        let (a, b) = self.parts();
+        let (c, d) = rhs.parts();
+
+        Self::reduce(a * d - c * b, b * d)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        let (a, b) = self.parts();
+        let (c, d) = rhs.parts();
+
+        Self::reduce(a * c, b * d)
+    }
+
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        let (a, b) = self.parts();
+        let (c, d) = rhs.parts();
+
+        Self::reduce(a * d, b * c)
+    }
+
+    fn checked_div_euclid(&self, rhs: &Self) -> Option<Self> {
+        let quotient = self.checked_div(rhs)?;
+
+        // Round towards negative infinity for positive divisors and towards positive infinity
+        // for negative ones, so that the remainder is never negative.
+        if rhs.numer > 0 {
+            Some(quotient.floor())
+        } else {
+            quotient.checked_neg()?.floor().checked_neg()
+        }
+    }
+
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self> {
+        let quotient = self.checked_div_euclid(rhs)?;
+        self.checked_sub(&rhs.checked_mul(&quotient)?)
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(Self {
+            numer: self.numer.checked_neg()?,
+            denom: self.denom,
+        })
+    }
+
+    // Only integral exponents are supported, since anything else is usually irrational.
+    fn checked_pow(&self, exponent: &Self) -> Option<Self> {
+        if exponent.denom != 1 {
+            return None;
+        }
+
+        let base = if exponent.numer < 0 {
+            Self::new(self.denom, self.numer)?
+        } else {
+            *self
+        };
+
+        let mut exponent = exponent.numer.unsigned_abs();
+
+        // Powers of 0, 1 and -1 never grow, so they are worked out directly however large the
+        // exponent is.
+        if base.denom == 1 && matches!(base.numer, -1..=1) {
+            let value = match base.numer {
+                0 if exponent > 0 => 0,
+                -1 if exponent % 2 == 1 => -1,
+                _ => 1,
+            };
+
+            return Some(Self::from(value));
+        }
+
+        // Exponentiation by squaring. Any other base overflows within a few dozen squarings.
+        let mut result = Self::from(1);
+        let mut square = base;
+
+        loop {
+            if exponent % 2 == 1 {
+                result = result.checked_mul(&square)?;
+            }
+
+            exponent /= 2;
+
+            if exponent == 0 {
+                return Some(result);
+            }
+
+            square = square.checked_mul(&square)?;
+        }
+    }
+
+    // Only perfect squares have rational square roots.
+    fn checked_sqrt(&self) -> Option<Self> {
+        let numer = u128::try_from(self.numer).ok()?;
+        let denom = u128::try_from(self.denom).ok()?;
+
+        let (numer_sqrt, denom_sqrt) = (isqrt(numer), isqrt(denom));
+
+        if numer_sqrt * numer_sqrt == numer && denom_sqrt * denom_sqrt == denom {
+            Self::reduce(numer_sqrt as i128, denom_sqrt as i128)
+        } else {
+            None
+        }
+    }
+
+    fn floor(&self) -> Self {
+        Self::from(self.numer.div_euclid(self.denom))
+    }
+
+    fn to_integer(&self) -> Option<u128> {
+        if self.denom == 1 {
+            u128::try_from(self.numer).ok()
+        } else {
+            None
+        }
+    }
+}
+
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Newton’s method converges from above, so start with a power of two that’s at least the
+    // square root.
+    let num_bits = 128 - n.leading_zeros();
+    let mut x = 1 << num_bits.div_ceil(2);
+
+    loop {
+        let y = (x + n / x) / 2;
+
+        if y >= x {
+            return x;
+        }
+
+        x = y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn rational(numer: i64, denom: i64) -> Rational {
+        Rational::new(numer, denom).unwrap()
+    }
+
+    #[test]
+    fn rationals_are_kept_in_lowest_terms() {
+        assert_eq!(rational(6, -4), rational(-3, 2));
+        assert_eq!(rational(-3, 2).numer(), -3);
+        assert_eq!(rational(-3, 2).denom(), 2);
+    }
+
+    #[test]
+    fn rational_with_zero_denominator_is_none() {
+        assert_eq!(Rational::new(1, 0), None);
+    }
+
+    #[test]
+    fn rational_arithmetic_is_exact() {
+        let third = rational(1, 3);
+        let sixth = rational(1, 6);
+
+        assert_eq!(third.checked_add(&sixth), Some(rational(1, 2)));
+        assert_eq!(third.checked_sub(&sixth), Some(rational(1, 6)));
+        assert_eq!(third.checked_mul(&sixth), Some(rational(1, 18)));
+        assert_eq!(third.checked_div(&sixth), Some(rational(2, 1)));
+    }
+
+    #[test]
+    fn rational_overflow_is_none() {
+        let large = Rational::from(i64::MAX);
+
+        assert_eq!(large.checked_add(&large), None);
+        assert_eq!(Rational::from(i64::MIN).checked_neg(), None);
+    }
+
+    #[test]
+    fn rationals_are_ordered_by_value() {
+        assert!(rational(1, 3) < rational(1, 2));
+        assert!(rational(-1, 2) < rational(-1, 3));
+    }
+
+    #[test]
+    fn integer_powers_of_zero_and_one_allow_huge_exponents() {
+        assert_eq!(Numeric::checked_pow(&0_i64, &5_000_000_000), Some(0));
+        assert_eq!(Numeric::checked_pow(&1_i64, &5_000_000_000), Some(1));
+        assert_eq!(Numeric::checked_pow(&-1_i64, &5_000_000_000), Some(1));
+        assert_eq!(Numeric::checked_pow(&-1_i64, &5_000_000_001), Some(-1));
+        assert_eq!(Numeric::checked_pow(&1_u64, &5_000_000_000), Some(1));
+        assert_eq!(Numeric::checked_pow(&2_i64, &5_000_000_000), None);
+    }
+
+    #[test]
+    fn rational_powers_with_integral_exponents() {
+        assert_eq!(
+            rational(2, 3).checked_pow(&rational(-2, 1)),
+            Some(rational(9, 4)),
+        );
+        assert_eq!(rational(2, 1).checked_pow(&rational(1, 2)), None);
+    }
+
+    #[test]
+    fn rational_powers_with_huge_exponents_finish() {
+        let huge = rational(i64::MAX, 1);
+
+        assert_eq!(rational(0, 1).checked_pow(&huge), Some(rational(0, 1)));
+        assert_eq!(rational(1, 1).checked_pow(&huge), Some(rational(1, 1)));
+        assert_eq!(rational(-1, 1).checked_pow(&huge), Some(rational(-1, 1)));
+        assert_eq!(
+            rational(-1, 1).checked_pow(&rational(-4, 1)),
+            Some(rational(1, 1))
+        );
+        assert_eq!(rational(0, 1).checked_pow(&rational(-1, 1)), None);
+        assert_eq!(rational(2, 1).checked_pow(&huge), None);
+        assert_eq!(
+            rational(3, 2).checked_pow(&rational(5, 1)),
+            Some(rational(243, 32))
+        );
+    }
+
+    #[test]
+    fn rational_square_roots_of_perfect_squares() {
+        assert_eq!(rational(9, 4).checked_sqrt(), Some(rational(3, 2)));
+        assert_eq!(rational(2, 1).checked_sqrt(), None);
+    }
+
+    #[test]
+    fn rationals_floor_towards_negative_infinity() {
+        assert_eq!(rational(-3, 2).floor(), rational(-2, 1));
+        assert_eq!(rational(7, 2).floor(), rational(3, 1));
+    }
+
+    #[test]
+    fn integer_square_roots_round_down() {
+        assert_eq!(Numeric::checked_sqrt(&17_i64), Some(4));
+        assert_eq!(Numeric::checked_sqrt(&u64::MAX), Some(u64::from(u32::MAX)));
+        assert_eq!(Numeric::checked_sqrt(&-1_i64), None);
+    }
+
+    #[test]
+    fn absolute_value_of_minimum_integer_is_none() {
+        assert_eq!(Numeric::checked_abs(&-5_i64), Some(5));
+        assert_eq!(Numeric::checked_abs(&i64::MIN), None);
+    }
+
+    #[test]
+    fn rational_display_omits_unit_denominator() {
+        assert_eq!(rational(4, 2).to_string(), "2");
+        assert_eq!(rational(-2, 4).to_string(), "-1/2");
+    }
+
+    #[test]
+    fn unsigned_subtraction_below_zero_is_none() {
+        assert_eq!(Numeric::checked_sub(&1_u64, &2), None);
+        assert_eq!(Numeric::checked_neg(&1_u64), None);
+    }
+
+    #[test]
+    fn non_finite_float_results_are_none() {
+        assert_eq!(Numeric::checked_mul(&f64::MAX, &2.0), None);
+        assert_eq!(Numeric::checked_div(&1.0, &4.0), Some(0.25));
+    }
+
+    fn literal(significand: u128, exponent: i32) -> Literal {
+        Literal {
+            significand,
+            exponent,
+        }
+    }
+
+    #[test]
+    fn integers_accept_literals_with_integral_values() {
+        assert_eq!(i64::from_literal(literal(15, 2)), Some(1500));
+        assert_eq!(i64::from_literal(literal(1500, -2)), Some(15));
+        assert_eq!(i64::from_literal(literal(314, -2)), None);
+    }
+
+    #[test]
+    fn floats_accept_fractional_literals() {
+        assert_eq!(f64::from_literal(literal(602, -25)), Some(6.02e-23));
+        assert_eq!(f64::from_literal(literal(1, 400)), None);
+    }
+
+    #[test]
+    fn rationals_represent_decimal_literals_exactly() {
+        assert_eq!(
+            Rational::from_literal(literal(125, -3)),
+            Some(rational(1, 8)),
+        );
+    }
+
+    #[test]
+    fn literals_that_do_not_fit_are_none() {
+        assert_eq!(<i64 as Numeric>::from_integer(u128::from(u64::MAX)), None);
+        assert_eq!(
+            <u64 as Numeric>::from_integer(u128::from(u64::MAX)),
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn only_non_negative_whole_numbers_convert_to_integers() {
+        assert_eq!(7_i64.to_integer(), Some(7));
+        assert_eq!((-1_i64).to_integer(), None);
+        assert_eq!(2.0_f64.to_integer(), Some(2));
+        assert_eq!(2.5_f64.to_integer(), None);
+        assert_eq!(rational(6, 3).to_integer(), Some(2));
+        assert_eq!(rational(1, 2).to_integer(), None);
+    }
+}
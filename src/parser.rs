@@ -1,11 +1,17 @@
-use crate::ast::Root;
-use crate::errors::{SyntaxError, SyntaxErrorKind};
-use crate::lexer::{Lexer, SyntaxKind};
+use crate::ast::{Context, Root};
+use crate::env::{EmptyEnvironment, Environment};
+use crate::errors::{EvalError, SyntaxError, SyntaxErrorKind};
+use crate::functions::FunctionRegistry;
+use crate::lexer::{self, Lexer, SyntaxKind};
+use crate::literal::{self, LiteralError};
+use crate::numeric::Numeric;
+use crate::value::Value;
 use crate::{Op, SyntaxNode};
 use codespan_reporting::diagnostic::Diagnostic;
 use rowan::{GreenNode, GreenNodeBuilder};
+use std::convert::TryFrom;
 use std::iter::Peekable;
-use text_size::TextRange;
+use text_size::{TextRange, TextSize};
 
 pub struct Parse {
     green_node: GreenNode,
@@ -13,13 +19,41 @@ pub struct Parse {
 }
 
 impl Parse {
-    fn syntax(&self) -> SyntaxNode {
+    pub fn syntax(&self) -> SyntaxNode {
         SyntaxNode::new_root(self.green_node.clone())
     }
 
-    pub fn eval(&self) -> Option<u32> {
+    pub fn root(&self) -> Root {
         // Parse will always contain a Root node, so we can unwrap.
-        Root::cast(self.syntax()).unwrap().eval()
+        Root::cast(self.syntax()).unwrap()
+    }
+
+    pub fn eval(&self) -> Result<Value, EvalError> {
+        self.eval_with()
+    }
+
+    pub fn eval_with<T: Numeric + 'static>(&self) -> Result<Value<T>, EvalError> {
+        self.eval_in(&EmptyEnvironment)
+    }
+
+    pub fn eval_in<T: Numeric + 'static>(
+        &self,
+        env: &dyn Environment<T>,
+    ) -> Result<Value<T>, EvalError> {
+        self.eval_with_functions(env, &FunctionRegistry::builtins())
+    }
+
+    pub fn eval_with_functions<T: Numeric>(
+        &self,
+        env: &dyn Environment<T>,
+        functions: &FunctionRegistry<T>,
+    ) -> Result<Value<T>, EvalError> {
+        let ctx = Context::new(env, functions);
+        self.root().eval(&ctx)
+    }
+
+    pub fn syntax_errors(&self) -> &[SyntaxError] {
+        &self.errors
     }
 
     pub fn errors(&self) -> impl ExactSizeIterator<Item = String> + '_ {
@@ -43,19 +77,29 @@ impl Parse {
 }
 
 pub struct Parser<'a> {
+    input: &'a str,
     lexer: Peekable<Lexer<'a>>,
     builder: GreenNodeBuilder<'static>,
     errors: Vec<SyntaxError>,
     last_lexeme_range: TextRange,
+    // Where the last lexeme other than trivia ended, which is where missing closing delimiters and
+    // semicolons are suggested so that they don’t end up inside a trailing comment.
+    code_end: TextSize,
+    // How many calls to expr_bp are in progress, which bounds how deeply the parser (and later
+    // evaluation) recurses.
+    depth: usize,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(s: &'a str) -> Self {
         Self {
+            input: s,
             lexer: Lexer::new(s).peekable(),
             builder: GreenNodeBuilder::new(),
             errors: Vec::new(),
             last_lexeme_range: TextRange::default(),
+            code_end: TextSize::default(),
+            depth: 0,
         }
     }
 
@@ -68,23 +112,101 @@ impl<'a> Parser<'a> {
 
         self.builder.token(lexeme.kind.into(), lexeme.text);
         self.last_lexeme_range = lexeme.range;
+
+        if !lexeme.kind.is_trivia() {
+            self.code_end = lexeme.range.end();
+        }
     }
 
-    fn record_error(&mut self, kind: SyntaxErrorKind) {
-        let range = if let Some(lexeme) = self.lexer.next() {
-            self.builder.token(SyntaxKind::Error.into(), lexeme.text);
-            self.last_lexeme_range = lexeme.range;
+    // Eats lexemes up to the next one that is_expected accepts (or the end of the input), grouping
+    // them into a single ErrorNode with one error spanning the whole run.
+    fn skip_junk(
+        &mut self,
+        expected: &'static [SyntaxKind],
+        is_expected: impl Fn(SyntaxKind) -> bool,
+    ) {
+        let range = self.error_node(is_expected);
 
-            lexeme.range
-        } else {
-            self.last_lexeme_range
+        // Junk is shown as written, since that’s easier to spot than a description of each kind
+        // of lexeme in it.
+        let kind = SyntaxErrorKind::FoundTextExpected {
+            found: self.input[range].to_string(),
+            expected,
+        };
+
+        self.errors
+            .push(SyntaxError::new(kind, range).with_suggestion(range, ""));
+    }
+
+    // Wraps lexemes up to the next one that is_expected accepts in an ErrorNode, returning the
+    // range they cover. is_expected is called once for each lexeme other than trivia, in order.
+    fn error_node(&mut self, mut is_expected: impl FnMut(SyntaxKind) -> bool) -> TextRange {
+        self.builder.start_node(SyntaxKind::ErrorNode.into());
+
+        let start = self.lexer.peek().unwrap().range.start();
+        let mut end = start;
+
+        // Trivia between junk lexemes is part of the run, but trivia after it isn’t.
+        while self.peek().is_some_and(|kind| !is_expected(kind)) {
+            self.bump();
+            end = self.last_lexeme_range.end();
+
+            self.skip_trivia();
+        }
+
+        self.builder.finish_node();
+
+        TextRange::new(start, end)
+    }
+
+    // Reports that something else was expected next, without consuming anything.
+    fn report_missing(&mut self, expected: &'static [SyntaxKind]) {
+        let (kind, range) = match self.lexer.peek() {
+            Some(lexeme) => (
+                SyntaxErrorKind::FoundExpected {
+                    found: lexeme.kind,
+                    expected,
+                },
+                lexeme.range,
+            ),
+            None => (
+                SyntaxErrorKind::Expected { expected },
+                self.last_lexeme_range,
+            ),
         };
 
-        self.errors.push(SyntaxError { kind, range });
+        self.errors.push(SyntaxError::new(kind, range));
+    }
+
+    // Whether the error reported last starts at offset, in which case another error there would
+    // only repeat it.
+    fn is_reported_at(&self, offset: TextSize) -> bool {
+        self.errors
+            .last()
+            .is_some_and(|error| error.range.start() == offset)
+    }
+
+    // Attaches a fix to the error that was reported last.
+    fn suggest(&mut self, range: TextRange, replacement: &str) {
+        let error = self.errors.pop().unwrap();
+        self.errors.push(error.with_suggestion(range, replacement));
     }
 
-    fn skip_ws(&mut self) {
-        while self.peek() == Some(SyntaxKind::Whitespace) {
+    // Trivia is kept in whichever node is being built when it’s reached, so comments stay next to
+    // the code they are written beside.
+    fn skip_trivia(&mut self) {
+        while let Some(lexeme) = self.lexer.peek().filter(|lexeme| lexeme.kind.is_trivia()) {
+            if lexeme.kind == SyntaxKind::BlockComment
+                && lexer::block_comment_len(&lexeme.text[2..]).is_none()
+            {
+                let start = lexeme.range.start();
+
+                self.errors.push(SyntaxError::new(
+                    SyntaxErrorKind::UnterminatedComment,
+                    TextRange::new(start, start + TextSize::from(2)),
+                ));
+            }
+
             self.bump();
         }
     }
@@ -92,9 +214,64 @@ impl<'a> Parser<'a> {
     pub fn parse(mut self) -> Parse {
         self.builder.start_node(SyntaxKind::Root.into());
 
-        self.skip_ws();
-        self.expr_bp(0);
-        self.skip_ws();
+        self.skip_trivia();
+
+        // A program is a sequence of statements terminated by semicolons, followed by the
+        // expression whose value is the value of the whole program.
+        loop {
+            if self.peek() == Some(SyntaxKind::LetKw) {
+                self.let_stmt();
+            } else {
+                let checkpoint = self.builder.checkpoint();
+                self.expr_bp(0);
+
+                match self.peek() {
+                    Some(SyntaxKind::Semicolon) => {}
+                    // Only a statement can be followed by another, so the semicolon ending this
+                    // one must have been left out.
+                    Some(SyntaxKind::LetKw) => {
+                        let start = self.lexer.peek().unwrap().range.start();
+
+                        // The expression may have been cut short by `let`, which will already
+                        // have been reported as a missing operand.
+                        if !self.is_reported_at(start) {
+                            self.report_missing(&[SyntaxKind::Semicolon]);
+                            self.suggest(TextRange::empty(self.code_end), ";");
+                        }
+                    }
+                    _ => break,
+                }
+
+                self.builder
+                    .start_node_at(checkpoint, SyntaxKind::ExprStmt.into());
+
+                if self.peek() == Some(SyntaxKind::Semicolon) {
+                    self.bump();
+                }
+
+                self.builder.finish_node();
+            }
+
+            self.skip_trivia();
+        }
+
+        self.skip_trivia();
+
+        // The loop above stops at anything that can’t continue the program, such as an unmatched
+        // closing parenthesis. Whatever is left still goes in the tree, so that the tree always
+        // covers the entire input.
+        if self.peek().is_some() {
+            let range = self.error_node(|_| false);
+
+            // If nothing came before it, it will already have been reported as an unexpected
+            // start to the expression.
+            // There’s no suggestion, since the input is often code that belongs to the program, and
+            // it’s the unmatched lexeme before it that needs fixing.
+            if !self.is_reported_at(range.start()) {
+                self.errors
+                    .push(SyntaxError::new(SyntaxErrorKind::TrailingInput, range));
+            }
+        }
 
         self.builder.finish_node();
 
@@ -105,32 +282,132 @@ impl<'a> Parser<'a> {
     }
 
     fn expr_bp(&mut self, min_bp: u8) {
+        if self.depth == MAX_DEPTH {
+            self.skip_too_deeply_nested();
+
+            self.builder.start_node(SyntaxKind::Missing.into());
+            self.builder.finish_node();
+            return;
+        }
+
+        // Restored on the way out, since each postfix expression below nests what came before it
+        // one level deeper. Chains of binary operators are evaluated without recursing down their
+        // left-hand sides, so they don’t count.
+        let depth = self.depth;
+        self.depth += 1;
+
         let checkpoint = self.builder.checkpoint();
 
+        // An operand missing after junk has already been reported along with the junk.
+        let mut skipped_junk = false;
+
         loop {
             match self.peek() {
                 Some(SyntaxKind::Number) => {
+                    self.literal(literal::parse);
+                    break;
+                }
+                Some(SyntaxKind::String) => {
+                    self.literal(literal::parse_string);
+                    break;
+                }
+                Some(SyntaxKind::TrueKw) | Some(SyntaxKind::FalseKw) => {
                     self.bump();
                     break;
                 }
-                Some(kind) => {
-                    self.record_error(SyntaxErrorKind::FoundExpected {
-                        found: kind,
-                        expected: &[SyntaxKind::Number],
-                    });
+                Some(SyntaxKind::Ident) => {
+                    self.name_ref();
+                    break;
                 }
-                None => {
-                    self.record_error(SyntaxErrorKind::Expected {
-                        expected: &[SyntaxKind::Number],
+                Some(SyntaxKind::LParen) => {
+                    self.paren_expr();
+                    break;
+                }
+                Some(SyntaxKind::LBracket) => {
+                    self.list_expr();
+                    break;
+                }
+                Some(SyntaxKind::IfKw) => {
+                    self.conditional_expr();
+                    break;
+                }
+                Some(SyntaxKind::FnKw) => {
+                    self.lambda();
+                    break;
+                }
+                Some(SyntaxKind::Minus) => {
+                    self.prefix_expr(Op::Sub);
+                    break;
+                }
+                Some(SyntaxKind::Plus) => {
+                    self.prefix_expr(Op::Add);
+                    break;
+                }
+                Some(SyntaxKind::Bang) => {
+                    self.prefix_expr(Op::Not);
+                    break;
+                }
+                Some(kind) if !is_operand_recovery(kind) => {
+                    self.skip_junk(EXPR_FIRST, |kind| {
+                        EXPR_FIRST.contains(&kind) || is_operand_recovery(kind)
                     });
-                    return;
+                    skipped_junk = true;
+                }
+                // Rather than eating something the surrounding code needs, mark where the operand
+                // should have been with an empty node and carry on as though it were there.
+                _ => {
+                    if !skipped_junk {
+                        self.report_missing(EXPR_FIRST);
+                    }
+
+                    self.builder.start_node(SyntaxKind::Missing.into());
+                    self.builder.finish_node();
+                    break;
                 }
             }
         }
 
-        self.skip_ws();
+        self.skip_trivia();
+
+        // Whether the expression built so far at this level is itself a comparison, used to reject
+        // chains like a < b < c.
+        let mut lhs_is_comparison = false;
+
+        'operators: loop {
+            let postfix = match self.peek() {
+                Some(SyntaxKind::LParen) => Some(SyntaxKind::CallExpr),
+                Some(SyntaxKind::LBracket) => Some(SyntaxKind::IndexExpr),
+                _ => None,
+            };
+
+            if let Some(postfix) = postfix {
+                let (left_bp, ()) = postfix_bp();
+
+                if left_bp < min_bp {
+                    break;
+                }
+
+                if self.depth == MAX_DEPTH {
+                    self.skip_too_deeply_nested();
+                    break;
+                }
+
+                self.depth += 1;
+                self.builder.start_node_at(checkpoint, postfix.into());
+
+                if postfix == SyntaxKind::CallExpr {
+                    self.arg_list();
+                } else {
+                    self.index();
+                }
+
+                self.builder.finish_node();
+
+                lhs_is_comparison = false;
+                self.skip_trivia();
+                continue;
+            }
 
-        loop {
             let op = loop {
                 match self.peek() {
                     Some(SyntaxKind::Plus) => {
@@ -145,18 +422,46 @@ impl<'a> Parser<'a> {
                     Some(SyntaxKind::Minus) => {
                         break Op::Sub;
                     }
-                    Some(kind) => {
-                        self.record_error(SyntaxErrorKind::FoundExpected {
-                            found: kind,
-                            expected: &[
-                                SyntaxKind::Plus,
-                                SyntaxKind::Star,
-                                SyntaxKind::Slash,
-                                SyntaxKind::Minus,
-                            ],
+                    Some(SyntaxKind::DivKw) => {
+                        break Op::DivEuclid;
+                    }
+                    Some(SyntaxKind::Percent) => {
+                        break Op::RemEuclid;
+                    }
+                    Some(SyntaxKind::Caret) => {
+                        break Op::Pow;
+                    }
+                    Some(SyntaxKind::EqEq) => {
+                        break Op::Eq;
+                    }
+                    Some(SyntaxKind::BangEq) => {
+                        break Op::Ne;
+                    }
+                    Some(SyntaxKind::Less) => {
+                        break Op::Lt;
+                    }
+                    Some(SyntaxKind::LessEq) => {
+                        break Op::Le;
+                    }
+                    Some(SyntaxKind::Greater) => {
+                        break Op::Gt;
+                    }
+                    Some(SyntaxKind::GreaterEq) => {
+                        break Op::Ge;
+                    }
+                    Some(SyntaxKind::AmpAmp) => {
+                        break Op::And;
+                    }
+                    Some(SyntaxKind::PipePipe) => {
+                        break Op::Or;
+                    }
+                    Some(kind) if EXPR_FOLLOW.contains(&kind) => break 'operators,
+                    Some(_) => {
+                        self.skip_junk(INFIX_OPERATORS, |kind| {
+                            INFIX_OPERATORS.contains(&kind) || EXPR_FOLLOW.contains(&kind)
                         });
                     }
-                    None => return,
+                    None => break 'operators,
                 }
             };
 
@@ -166,160 +471,2019 @@ impl<'a> Parser<'a> {
                 break;
             }
 
+            // Comparisons are non-associative. We still build the (left-nested) tree so that
+            // parsing can carry on, but point out the operator that continues the chain.
+            if lhs_is_comparison && is_comparison(op) {
+                let range = self.lexer.peek().unwrap().range;
+                self.errors
+                    .push(SyntaxError::new(SyntaxErrorKind::ChainedComparison, range));
+            }
+
             // Only continue building the syntax tree after potentially breaking out of the loop to
             // prevent a half-built syntax tree.
 
             self.builder
                 .start_node_at(checkpoint, SyntaxKind::Operation.into());
 
-            // Eat the operator’s token and any whitespace following it.
+            // Eat the operator’s token and any trivia following it.
             self.bump();
-            self.skip_ws();
+            self.skip_trivia();
 
             self.expr_bp(right_bp);
 
             self.builder.finish_node();
+
+            lhs_is_comparison = is_comparison(op);
         }
-    }
-}
 
-fn infix_bp(op: Op) -> (u8, u8) {
-    match op {
-        Op::Add | Op::Sub => (1, 2),
-        Op::Mul | Op::Div => (3, 4),
+        self.depth = depth;
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use pretty_assertions::assert_eq;
+    // Reports whatever would be nested past MAX_DEPTH, skipping it up to where an enclosing
+    // expression can carry on. Delimiters are kept track of so that deeply nested but otherwise
+    // well-formed input gets a single error.
+    fn skip_too_deeply_nested(&mut self) {
+        let range = if self.peek().is_some_and(|kind| !EXPR_FOLLOW.contains(&kind)) {
+            let mut open = 0_usize;
 
-    #[test]
-    fn parse_single_number() {
-        let parse = Parser::new("1").parse();
+            self.error_node(|kind| match kind {
+                SyntaxKind::LParen | SyntaxKind::LBracket => {
+                    open += 1;
+                    false
+                }
+                SyntaxKind::RParen | SyntaxKind::RBracket if open > 0 => {
+                    open -= 1;
+                    false
+                }
+                kind => open == 0 && EXPR_FOLLOW.contains(&kind),
+            })
+        } else {
+            self.last_lexeme_range
+        };
 
-        assert_eq!(
-            parse.format(),
-            r#"Root@0..1
-  Number@0..1 "1"
-"#,
-        );
+        self.errors
+            .push(SyntaxError::new(SyntaxErrorKind::NestedTooDeeply, range));
     }
 
-    #[test]
-    fn parse_simple_binary_operation() {
-        let parse = Parser::new("1+1").parse();
+    // Validates a number or string literal, reporting errors at their exact range within it.
+    fn literal<T>(&mut self, parse: fn(&str) -> Result<T, LiteralError>) {
+        let lexeme = self.lexer.peek().unwrap();
 
-        assert_eq!(
-            parse.format(),
-            r#"Root@0..3
-  Operation@0..3
-    Number@0..1 "1"
-    Plus@1..2 "+"
-    Number@2..3 "1"
-"#,
-        );
-    }
+        if let Err(LiteralError::Malformed { kind, range }) = parse(&lexeme.text) {
+            let start = lexeme.range.start();
+            let range = TextRange::new(
+                start + TextSize::try_from(range.start).unwrap(),
+                start + TextSize::try_from(range.end).unwrap(),
+            );
 
-    #[test]
-    fn multiplication_has_higher_precedence_than_addition() {
-        let parse = Parser::new("1+2*3").parse();
+            // Only fixes that are certain to be what was meant are suggested. For instance, a
+            // string is only closed if that’s all it takes to make it valid.
+            let fix = match kind {
+                SyntaxErrorKind::MissingFractionDigits => Some((range.end(), "0")),
+                SyntaxErrorKind::UnterminatedString
+                    if parse(&format!("{}\"", lexeme.text)).is_ok() =>
+                {
+                    Some((lexeme.range.end(), "\""))
+                }
+                _ => None,
+            };
 
-        assert_eq!(
-            parse.format(),
-            r#"Root@0..5
-  Operation@0..5
-    Number@0..1 "1"
-    Plus@1..2 "+"
-    Operation@2..5
-      Number@2..3 "2"
-      Star@3..4 "*"
-      Number@4..5 "3"
-"#,
-        );
-    }
+            let mut error = SyntaxError::new(kind, range);
 
-    #[test]
-    fn subtraction_is_left_associative() {
-        let parse = Parser::new("10-7-3").parse();
+            if let Some((offset, replacement)) = fix {
+                error = error.with_suggestion(TextRange::empty(offset), replacement);
+            }
 
-        assert_eq!(
-            parse.format(),
-            r#"Root@0..6
-  Operation@0..6
-    Operation@0..4
-      Number@0..2 "10"
-      Minus@2..3 "-"
-      Number@3..4 "7"
-    Minus@4..5 "-"
-    Number@5..6 "3"
-"#,
-        );
+            self.errors.push(error);
+        }
+
+        self.bump();
     }
 
-    #[test]
-    fn whitespace_is_skipped() {
-        let parse = Parser::new(" 14 +26- 27 /  3 * 2 ").parse();
+    fn name_ref(&mut self) {
+        self.builder.start_node(SyntaxKind::NameRef.into());
+        self.bump();
+        self.builder.finish_node();
+    }
 
-        assert_eq!(
-            parse.format(),
-            r#"Root@0..21
-  Whitespace@0..1 " "
-  Operation@1..21
-    Operation@1..7
-      Number@1..3 "14"
-      Whitespace@3..4 " "
-      Plus@4..5 "+"
-      Number@5..7 "26"
-    Minus@7..8 "-"
-    Whitespace@8..9 " "
-    Operation@9..21
-      Operation@9..17
-        Number@9..11 "27"
-        Whitespace@11..12 " "
-        Slash@12..13 "/"
-        Whitespace@13..15 "  "
-        Number@15..16 "3"
-        Whitespace@16..17 " "
-      Star@17..18 "*"
-      Whitespace@18..19 " "
-      Number@19..20 "2"
-      Whitespace@20..21 " "
-"#,
-        );
+    fn prefix_expr(&mut self, op: Op) {
+        self.builder.start_node(SyntaxKind::PrefixExpr.into());
+
+        let ((), right_bp) = prefix_bp(op);
+
+        // Eat the operator’s token and any trivia following it.
+        self.bump();
+        self.skip_trivia();
+
+        // Runs of prefix operators nest, so going through expr_bp keeps them within MAX_DEPTH.
+        self.expr_bp(right_bp);
+
+        self.builder.finish_node();
     }
 
-    #[test]
-    fn junk_before_numbers_is_skipped() {
-        let parse = Parser::new("abc1").parse();
+    fn arg_list(&mut self) {
+        self.builder.start_node(SyntaxKind::ArgList.into());
+        self.delimited_exprs(SyntaxKind::RParen);
+        self.builder.finish_node();
+    }
 
-        assert_eq!(
-            parse.format(),
-            r#"Root@0..4
-  Error@0..1 "a"
-  Error@1..2 "b"
-  Error@2..3 "c"
-  Number@3..4 "1"
-"#,
-        );
+    fn list_expr(&mut self) {
+        self.builder.start_node(SyntaxKind::ListExpr.into());
+        self.delimited_exprs(SyntaxKind::RBracket);
+        self.builder.finish_node();
     }
 
-    #[test]
-    fn junk_before_operators_is_skipped() {
-        let parse = Parser::new("1 a+ 2").parse();
+    // Eats an opening delimiter, then expressions separated by commas (with an optional trailing
+    // comma) up to and including the closing delimiter.
+    fn delimited_exprs(&mut self, closing: SyntaxKind) {
+        // Eat the opening delimiter and any trivia following it.
+        self.bump();
+        let opening_range = self.last_lexeme_range;
+        self.skip_trivia();
 
-        assert_eq!(
-            parse.format(),
-            r#"Root@0..6
-  Operation@0..6
-    Number@0..1 "1"
-    Whitespace@1..2 " "
-    Error@2..3 "a"
-    Plus@3..4 "+"
-    Whitespace@4..5 " "
-    Number@5..6 "2"
-"#,
-        )
+        loop {
+            match self.peek() {
+                Some(kind) if kind == closing => {
+                    self.bump();
+                    break;
+                }
+                None => {
+                    self.report_unclosed(closing, opening_range);
+                    break;
+                }
+                Some(_) => {}
+            }
+
+            self.expr_bp(0);
+
+            if self.peek() == Some(SyntaxKind::Comma) {
+                self.bump();
+                self.skip_trivia();
+            } else if self.peek() != Some(closing) {
+                self.report_unclosed(closing, opening_range);
+                break;
+            }
+        }
+    }
+
+    fn index(&mut self) {
+        // Eat the opening bracket and any trivia following it.
+        self.bump();
+        let opening_bracket_range = self.last_lexeme_range;
+        self.skip_trivia();
+
+        self.expr_bp(0);
+
+        if self.peek() == Some(SyntaxKind::RBracket) {
+            self.bump();
+        } else {
+            self.report_unclosed(SyntaxKind::RBracket, opening_bracket_range);
+        }
+    }
+
+    // Unclosed delimiters are reported at the opening delimiter, since that’s what needs a
+    // partner.
+    fn report_unclosed(&mut self, closing: SyntaxKind, opening_range: TextRange) {
+        let kind = match closing {
+            SyntaxKind::RParen => SyntaxErrorKind::UnclosedParen,
+            SyntaxKind::RBracket => SyntaxErrorKind::UnclosedBracket,
+            _ => unreachable!(),
+        };
+
+        let closing_text = match closing {
+            SyntaxKind::RParen => ")",
+            _ => "]",
+        };
+
+        self.errors.push(
+            SyntaxError::new(kind, opening_range)
+                .with_suggestion(TextRange::empty(self.code_end), closing_text),
+        );
+    }
+
+    fn let_stmt(&mut self) {
+        self.builder.start_node(SyntaxKind::LetStmt.into());
+
+        // Eat `let` and any trivia following it.
+        self.bump();
+        self.skip_trivia();
+
+        if self.peek() == Some(SyntaxKind::Ident) {
+            self.bump();
+            self.skip_trivia();
+        } else {
+            self.report_missing(&[SyntaxKind::Ident]);
+        }
+
+        if self.peek() == Some(SyntaxKind::Equals) {
+            self.bump();
+            self.skip_trivia();
+        } else {
+            self.report_missing(&[SyntaxKind::Equals]);
+        }
+
+        self.expr_bp(0);
+
+        if self.peek() == Some(SyntaxKind::Semicolon) {
+            self.bump();
+        } else {
+            self.report_missing(&[SyntaxKind::Semicolon]);
+            self.suggest(TextRange::empty(self.code_end), ";");
+        }
+
+        self.builder.finish_node();
+    }
+
+    fn lambda(&mut self) {
+        self.builder.start_node(SyntaxKind::Lambda.into());
+
+        // Eat `fn` and any trivia following it.
+        self.bump();
+        self.skip_trivia();
+
+        if self.peek() == Some(SyntaxKind::LParen) {
+            self.param_list();
+        } else {
+            self.report_missing(&[SyntaxKind::LParen]);
+        }
+
+        // Like the else branch of a conditional, the body extends as far to the right as possible.
+        self.expr_bp(0);
+
+        self.builder.finish_node();
+    }
+
+    fn param_list(&mut self) {
+        self.builder.start_node(SyntaxKind::ParamList.into());
+
+        // Eat the opening parenthesis and any trivia following it.
+        self.bump();
+        let opening_paren_range = self.last_lexeme_range;
+        self.skip_trivia();
+
+        loop {
+            match self.peek() {
+                Some(SyntaxKind::RParen) => {
+                    self.bump();
+                    break;
+                }
+                Some(SyntaxKind::Ident) => {
+                    self.bump();
+                    self.skip_trivia();
+                }
+                None => {
+                    self.report_unclosed(SyntaxKind::RParen, opening_paren_range);
+                    break;
+                }
+                Some(_) => {
+                    self.report_missing(&[SyntaxKind::Ident, SyntaxKind::RParen]);
+                    break;
+                }
+            }
+
+            // Parameters are separated by commas, with an optional trailing comma before the
+            // closing parenthesis.
+            if self.peek() == Some(SyntaxKind::Comma) {
+                self.bump();
+                self.skip_trivia();
+            } else if self.peek() != Some(SyntaxKind::RParen) {
+                self.report_missing(&[SyntaxKind::Comma, SyntaxKind::RParen]);
+                break;
+            }
+        }
+
+        self.skip_trivia();
+        self.builder.finish_node();
+    }
+
+    fn conditional_expr(&mut self) {
+        self.builder.start_node(SyntaxKind::ConditionalExpr.into());
+
+        // Eat `if` and any trivia following it.
+        self.bump();
+        self.skip_trivia();
+
+        self.expr_bp(0);
+
+        if self.peek() == Some(SyntaxKind::ThenKw) {
+            self.bump();
+            self.skip_trivia();
+        } else {
+            self.report_missing(&[SyntaxKind::ThenKw]);
+        }
+
+        // If `then` was missing we might already be at `else`, in which case there is no then
+        // branch to parse.
+        if self.peek() != Some(SyntaxKind::ElseKw) {
+            self.expr_bp(0);
+        }
+
+        // Without `else` we stop here rather than guessing where the else branch would be, leaving
+        // the incomplete conditional for evaluation to reject.
+        if self.peek() == Some(SyntaxKind::ElseKw) {
+            self.bump();
+            self.skip_trivia();
+
+            // The else branch extends as far to the right as possible, making conditionals both
+            // lowest-precedence and right-associative.
+            self.expr_bp(0);
+        } else {
+            self.report_missing(&[SyntaxKind::ElseKw]);
+        }
+
+        self.builder.finish_node();
+    }
+
+    fn paren_expr(&mut self) {
+        self.builder.start_node(SyntaxKind::ParenExpr.into());
+
+        // Eat the opening parenthesis and any trivia following it.
+        self.bump();
+        let opening_paren_range = self.last_lexeme_range;
+        self.skip_trivia();
+
+        // Parentheses reset the binding power, since whatever is inside them is parsed as a
+        // standalone expression.
+        self.expr_bp(0);
+
+        if self.peek() == Some(SyntaxKind::RParen) {
+            self.bump();
+        } else {
+            self.report_unclosed(SyntaxKind::RParen, opening_paren_range);
+        }
+
+        self.builder.finish_node();
+    }
+}
+
+// Deep enough for any expression written by hand, but shallow enough that neither parsing nor
+// evaluation can overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+// The kinds that can begin an expression.
+const EXPR_FIRST: &[SyntaxKind] = &[
+    SyntaxKind::Number,
+    SyntaxKind::String,
+    SyntaxKind::TrueKw,
+    SyntaxKind::FalseKw,
+    SyntaxKind::Ident,
+    SyntaxKind::LParen,
+    SyntaxKind::LBracket,
+    SyntaxKind::Minus,
+    SyntaxKind::Plus,
+    SyntaxKind::Bang,
+    SyntaxKind::IfKw,
+    SyntaxKind::FnKw,
+];
+
+// The binary operators that can follow an operand.
+const INFIX_OPERATORS: &[SyntaxKind] = &[
+    SyntaxKind::Plus,
+    SyntaxKind::Star,
+    SyntaxKind::Slash,
+    SyntaxKind::Minus,
+    SyntaxKind::DivKw,
+    SyntaxKind::Percent,
+    SyntaxKind::Caret,
+    SyntaxKind::EqEq,
+    SyntaxKind::BangEq,
+    SyntaxKind::Less,
+    SyntaxKind::LessEq,
+    SyntaxKind::Greater,
+    SyntaxKind::GreaterEq,
+    SyntaxKind::AmpAmp,
+    SyntaxKind::PipePipe,
+];
+
+// Closing delimiters, commas, semicolons, the keywords separating the parts of a conditional and
+// the `let` starting the next statement are left for whatever encloses an expression to eat.
+const EXPR_FOLLOW: &[SyntaxKind] = &[
+    SyntaxKind::RParen,
+    SyntaxKind::RBracket,
+    SyntaxKind::Comma,
+    SyntaxKind::ThenKw,
+    SyntaxKind::ElseKw,
+    SyntaxKind::Semicolon,
+    SyntaxKind::LetKw,
+];
+
+// When an operand is missing, these kinds are kept rather than skipped over as junk, since parsing
+// can carry on from them: they either continue the expression around the hole or end it.
+fn is_operand_recovery(kind: SyntaxKind) -> bool {
+    EXPR_FOLLOW.contains(&kind) || INFIX_OPERATORS.contains(&kind)
+}
+
+fn is_comparison(op: Op) -> bool {
+    matches!(op, Op::Eq | Op::Ne | Op::Lt | Op::Le | Op::Gt | Op::Ge)
+}
+
+fn prefix_bp(op: Op) -> ((), u8) {
+    match op {
+        Op::Add | Op::Sub | Op::Not => ((), 11),
+        _ => unreachable!(),
+    }
+}
+
+fn postfix_bp() -> (u8, ()) {
+    (15, ())
+}
+
+fn infix_bp(op: Op) -> (u8, u8) {
+    match op {
+        Op::Or => (1, 2),
+        Op::And => (3, 4),
+        Op::Eq | Op::Ne | Op::Lt | Op::Le | Op::Gt | Op::Ge => (5, 6),
+        Op::Add | Op::Sub => (7, 8),
+        Op::Mul | Op::Div | Op::DivEuclid | Op::RemEuclid => (9, 10),
+        // Exponentiation is right-associative, and binds tighter than prefix operators so that
+        // -2^2 is -(2^2).
+        Op::Pow => (14, 13),
+        Op::Not => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Suggestion;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+
+    #[test]
+    fn parse_single_number() {
+        let parse = Parser::new("1").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..1
+  Number@0..1 "1"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_simple_binary_operation() {
+        let parse = Parser::new("1+1").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..3
+  Operation@0..3
+    Number@0..1 "1"
+    Plus@1..2 "+"
+    Number@2..3 "1"
+"#,
+        );
+    }
+
+    #[test]
+    fn multiplication_has_higher_precedence_than_addition() {
+        let parse = Parser::new("1+2*3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..5
+  Operation@0..5
+    Number@0..1 "1"
+    Plus@1..2 "+"
+    Operation@2..5
+      Number@2..3 "2"
+      Star@3..4 "*"
+      Number@4..5 "3"
+"#,
+        );
+    }
+
+    #[test]
+    fn subtraction_is_left_associative() {
+        let parse = Parser::new("10-7-3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..6
+  Operation@0..6
+    Operation@0..4
+      Number@0..2 "10"
+      Minus@2..3 "-"
+      Number@3..4 "7"
+    Minus@4..5 "-"
+    Number@5..6 "3"
+"#,
+        );
+    }
+
+    #[test]
+    fn integer_division_and_modulo_are_multiplicative() {
+        let parse = Parser::new("1+7 div 2%3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..11
+  Operation@0..11
+    Number@0..1 "1"
+    Plus@1..2 "+"
+    Operation@2..11
+      Operation@2..9
+        Number@2..3 "7"
+        Whitespace@3..4 " "
+        DivKw@4..7 "div"
+        Whitespace@7..8 " "
+        Number@8..9 "2"
+      Percent@9..10 "%"
+      Number@10..11 "3"
+"#,
+        );
+    }
+
+    #[test]
+    fn exponentiation_is_right_associative() {
+        let parse = Parser::new("2^3^2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..5
+  Operation@0..5
+    Number@0..1 "2"
+    Caret@1..2 "^"
+    Operation@2..5
+      Number@2..3 "3"
+      Caret@3..4 "^"
+      Number@4..5 "2"
+"#,
+        );
+    }
+
+    #[test]
+    fn exponentiation_has_higher_precedence_than_negation() {
+        let parse = Parser::new("-2^2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..4
+  PrefixExpr@0..4
+    Minus@0..1 "-"
+    Operation@1..4
+      Number@1..2 "2"
+      Caret@2..3 "^"
+      Number@3..4 "2"
+"#,
+        );
+    }
+
+    #[test]
+    fn exponentiation_has_higher_precedence_than_multiplication() {
+        let parse = Parser::new("2*3^-2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..6
+  Operation@0..6
+    Number@0..1 "2"
+    Star@1..2 "*"
+    Operation@2..6
+      Number@2..3 "3"
+      Caret@3..4 "^"
+      PrefixExpr@4..6
+        Minus@4..5 "-"
+        Number@5..6 "2"
+"#,
+        );
+    }
+
+    #[test]
+    fn comparisons_have_lower_precedence_than_arithmetic() {
+        let parse = Parser::new("1+2<=3*4").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..8
+  Operation@0..8
+    Operation@0..3
+      Number@0..1 "1"
+      Plus@1..2 "+"
+      Number@2..3 "2"
+    LessEq@3..5 "<="
+    Operation@5..8
+      Number@5..6 "3"
+      Star@6..7 "*"
+      Number@7..8 "4"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn parenthesized_comparisons_can_be_compared() {
+        let parse = Parser::new("(1<2)==(3>4)").parse();
+
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn chained_comparisons_are_reported() {
+        let parse = Parser::new("1<2<3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..5
+  Operation@0..5
+    Operation@0..3
+      Number@0..1 "1"
+      Less@1..2 "<"
+      Number@2..3 "2"
+    Less@3..4 "<"
+    Number@4..5 "3"
+"#,
+        );
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["comparison operators cannot be chained"],
+        );
+    }
+
+    #[test]
+    fn chained_comparison_is_reported_at_second_operator() {
+        let parse = Parser::new("a == b + 1 != c").parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(11.into(), 13.into())]);
+    }
+
+    #[test]
+    fn logical_operators_have_conventional_precedence() {
+        let parse = Parser::new("a||b&&!c<d").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..10
+  Operation@0..10
+    NameRef@0..1
+      Ident@0..1 "a"
+    PipePipe@1..3 "||"
+    Operation@3..10
+      NameRef@3..4
+        Ident@3..4 "b"
+      AmpAmp@4..6 "&&"
+      Operation@6..10
+        PrefixExpr@6..8
+          Bang@6..7 "!"
+          NameRef@7..8
+            Ident@7..8 "c"
+        Less@8..9 "<"
+        NameRef@9..10
+          Ident@9..10 "d"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn parse_boolean_literals() {
+        let parse = Parser::new("true!=false").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..11
+  Operation@0..11
+    TrueKw@0..4 "true"
+    BangEq@4..6 "!="
+    FalseKw@6..11 "false"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_conditional() {
+        let parse = Parser::new("if a then 1 else 2+3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..20
+  ConditionalExpr@0..20
+    IfKw@0..2 "if"
+    Whitespace@2..3 " "
+    NameRef@3..4
+      Ident@3..4 "a"
+    Whitespace@4..5 " "
+    ThenKw@5..9 "then"
+    Whitespace@9..10 " "
+    Number@10..11 "1"
+    Whitespace@11..12 " "
+    ElseKw@12..16 "else"
+    Whitespace@16..17 " "
+    Operation@17..20
+      Number@17..18 "2"
+      Plus@18..19 "+"
+      Number@19..20 "3"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn conditionals_are_right_associative() {
+        let parse = Parser::new("if a then 1 else if b then 2 else 3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..35
+  ConditionalExpr@0..35
+    IfKw@0..2 "if"
+    Whitespace@2..3 " "
+    NameRef@3..4
+      Ident@3..4 "a"
+    Whitespace@4..5 " "
+    ThenKw@5..9 "then"
+    Whitespace@9..10 " "
+    Number@10..11 "1"
+    Whitespace@11..12 " "
+    ElseKw@12..16 "else"
+    Whitespace@16..17 " "
+    ConditionalExpr@17..35
+      IfKw@17..19 "if"
+      Whitespace@19..20 " "
+      NameRef@20..21
+        Ident@20..21 "b"
+      Whitespace@21..22 " "
+      ThenKw@22..26 "then"
+      Whitespace@26..27 " "
+      Number@27..28 "2"
+      Whitespace@28..29 " "
+      ElseKw@29..33 "else"
+      Whitespace@33..34 " "
+      Number@34..35 "3"
+"#,
+        );
+    }
+
+    #[test]
+    fn conditional_can_be_an_operand() {
+        let parse = Parser::new("2*if a then 1 else 3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..20
+  Operation@0..20
+    Number@0..1 "2"
+    Star@1..2 "*"
+    ConditionalExpr@2..20
+      IfKw@2..4 "if"
+      Whitespace@4..5 " "
+      NameRef@5..6
+        Ident@5..6 "a"
+      Whitespace@6..7 " "
+      ThenKw@7..11 "then"
+      Whitespace@11..12 " "
+      Number@12..13 "1"
+      Whitespace@13..14 " "
+      ElseKw@14..18 "else"
+      Whitespace@18..19 " "
+      Number@19..20 "3"
+"#,
+        );
+    }
+
+    #[test]
+    fn missing_else_is_reported() {
+        let parse = Parser::new("(if a then 1)*2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..15
+  Operation@0..15
+    ParenExpr@0..13
+      LParen@0..1 "("
+      ConditionalExpr@1..12
+        IfKw@1..3 "if"
+        Whitespace@3..4 " "
+        NameRef@4..5
+          Ident@4..5 "a"
+        Whitespace@5..6 " "
+        ThenKw@6..10 "then"
+        Whitespace@10..11 " "
+        Number@11..12 "1"
+      RParen@12..13 ")"
+    Star@13..14 "*"
+    Number@14..15 "2"
+"#,
+        );
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["found a right parenthesis, expected `else`"],
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(12.into(), 13.into())]);
+    }
+
+    #[test]
+    fn missing_else_at_end_of_input_is_reported() {
+        let parse = Parser::new("if a then 1").parse();
+
+        assert_eq!(parse.errors().collect::<Vec<_>>(), ["expected `else`"]);
+    }
+
+    #[test]
+    fn missing_then_is_reported() {
+        let parse = Parser::new("if a else 2").parse();
+
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["found `else`, expected `then`"],
+        );
+    }
+
+    #[test]
+    fn parse_let_statements() {
+        let parse = Parser::new("let x = 3; let y = x*2; y").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..25
+  LetStmt@0..10
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Ident@4..5 "x"
+    Whitespace@5..6 " "
+    Equals@6..7 "="
+    Whitespace@7..8 " "
+    Number@8..9 "3"
+    Semicolon@9..10 ";"
+  Whitespace@10..11 " "
+  LetStmt@11..23
+    LetKw@11..14 "let"
+    Whitespace@14..15 " "
+    Ident@15..16 "y"
+    Whitespace@16..17 " "
+    Equals@17..18 "="
+    Whitespace@18..19 " "
+    Operation@19..22
+      NameRef@19..20
+        Ident@19..20 "x"
+      Star@20..21 "*"
+      Number@21..22 "2"
+    Semicolon@22..23 ";"
+  Whitespace@23..24 " "
+  NameRef@24..25
+    Ident@24..25 "y"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn parse_expression_statements() {
+        let parse = Parser::new("1;2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..3
+  ExprStmt@0..2
+    Number@0..1 "1"
+    Semicolon@1..2 ";"
+  Number@2..3 "2"
+"#,
+        );
+    }
+
+    #[test]
+    fn let_without_name_is_reported() {
+        let parse = Parser::new("let = 3; 1").parse();
+
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["found an equals sign, expected an identifier"],
+        );
+    }
+
+    #[test]
+    fn let_without_semicolon_is_reported() {
+        let parse = Parser::new("let x = 3)").parse();
+
+        assert_eq!(
+            parse.errors().next().as_deref(),
+            Some("found a right parenthesis, expected a semicolon"),
+        );
+    }
+
+    #[test]
+    fn parse_lambda() {
+        let parse = Parser::new("fn(x, y) x*y").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..12
+  Lambda@0..12
+    FnKw@0..2 "fn"
+    ParamList@2..9
+      LParen@2..3 "("
+      Ident@3..4 "x"
+      Comma@4..5 ","
+      Whitespace@5..6 " "
+      Ident@6..7 "y"
+      RParen@7..8 ")"
+      Whitespace@8..9 " "
+    Operation@9..12
+      NameRef@9..10
+        Ident@9..10 "x"
+      Star@10..11 "*"
+      NameRef@11..12
+        Ident@11..12 "y"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn lambda_body_extends_to_the_right() {
+        let parse = Parser::new("1+fn(x) x+1").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..11
+  Operation@0..11
+    Number@0..1 "1"
+    Plus@1..2 "+"
+    Lambda@2..11
+      FnKw@2..4 "fn"
+      ParamList@4..8
+        LParen@4..5 "("
+        Ident@5..6 "x"
+        RParen@6..7 ")"
+        Whitespace@7..8 " "
+      Operation@8..11
+        NameRef@8..9
+          Ident@8..9 "x"
+        Plus@9..10 "+"
+        Number@10..11 "1"
+"#,
+        );
+    }
+
+    #[test]
+    fn lambda_without_parameter_list_is_reported() {
+        let parse = Parser::new("fn x").parse();
+
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["found an identifier, expected a left parenthesis"],
+        );
+    }
+
+    #[test]
+    fn non_identifier_parameter_is_reported() {
+        let parse = Parser::new("fn(1) 2").parse();
+
+        assert_eq!(
+            parse.errors().next().as_deref(),
+            Some("found a number literal, expected an identifier or a right parenthesis"),
+        );
+    }
+
+    #[test]
+    fn platform_and_unicode_whitespace_is_skipped() {
+        let parse = Parser::new("1\r\n+\t2\u{A0}").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..8
+  Operation@0..8
+    Number@0..1 "1"
+    Whitespace@1..3 "\r\n"
+    Plus@3..4 "+"
+    Whitespace@4..5 "\t"
+    Number@5..6 "2"
+    Whitespace@6..8 "\u{a0}"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn whitespace_is_skipped() {
+        let parse = Parser::new(" 14 +26- 27 /  3 * 2 ").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..21
+  Whitespace@0..1 " "
+  Operation@1..21
+    Operation@1..7
+      Number@1..3 "14"
+      Whitespace@3..4 " "
+      Plus@4..5 "+"
+      Number@5..7 "26"
+    Minus@7..8 "-"
+    Whitespace@8..9 " "
+    Operation@9..21
+      Operation@9..17
+        Number@9..11 "27"
+        Whitespace@11..12 " "
+        Slash@12..13 "/"
+        Whitespace@13..15 "  "
+        Number@15..16 "3"
+        Whitespace@16..17 " "
+      Star@17..18 "*"
+      Whitespace@18..19 " "
+      Number@19..20 "2"
+      Whitespace@20..21 " "
+"#,
+        );
+    }
+
+    #[test]
+    fn junk_before_numbers_is_skipped() {
+        let parse = Parser::new("@$~1").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..4
+  ErrorNode@0..3
+    Error@0..1 "@"
+    Error@1..2 "$"
+    Error@2..3 "~"
+  Number@3..4 "1"
+"#,
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(0.into(), 3.into())]);
+        assert!(parse
+            .errors()
+            .next()
+            .unwrap()
+            .starts_with("found `@$~`, expected"));
+    }
+
+    #[test]
+    fn missing_operand_keeps_both_operators() {
+        let parse = Parser::new("1 + * 2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..7
+  Operation@0..7
+    Number@0..1 "1"
+    Whitespace@1..2 " "
+    Plus@2..3 "+"
+    Whitespace@3..4 " "
+    Operation@4..7
+      Missing@4..4
+      Star@4..5 "*"
+      Whitespace@5..6 " "
+      Number@6..7 "2"
+"#,
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(4.into(), 5.into())]);
+    }
+
+    #[test]
+    fn missing_operand_at_end_of_input_leaves_a_hole() {
+        let parse = Parser::new("1 +").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..3
+  Operation@0..3
+    Number@0..1 "1"
+    Whitespace@1..2 " "
+    Plus@2..3 "+"
+    Missing@3..3
+"#,
+        );
+        assert_eq!(parse.errors().len(), 1);
+    }
+
+    #[test]
+    fn missing_argument_leaves_a_hole() {
+        let parse = Parser::new("f(1, , 2)").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..9
+  CallExpr@0..9
+    NameRef@0..1
+      Ident@0..1 "f"
+    ArgList@1..9
+      LParen@1..2 "("
+      Number@2..3 "1"
+      Comma@3..4 ","
+      Whitespace@4..5 " "
+      Missing@5..5
+      Comma@5..6 ","
+      Whitespace@6..7 " "
+      Number@7..8 "2"
+      RParen@8..9 ")"
+"#,
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(5.into(), 6.into())]);
+    }
+
+    #[test]
+    fn trivia_after_junk_is_not_reported() {
+        let parse = Parser::new("1 + @ 2").parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(4.into(), 5.into())]);
+    }
+
+    #[test]
+    fn junk_before_operators_is_skipped() {
+        let parse = Parser::new("1 @+ 2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..6
+  Operation@0..6
+    Number@0..1 "1"
+    Whitespace@1..2 " "
+    ErrorNode@2..3
+      Error@2..3 "@"
+    Plus@3..4 "+"
+    Whitespace@4..5 " "
+    Number@5..6 "2"
+"#,
+        )
+    }
+
+    #[test]
+    fn junk_run_is_one_error_excluding_trailing_trivia() {
+        let parse = Parser::new("1 + @ $ 2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..9
+  Operation@0..9
+    Number@0..1 "1"
+    Whitespace@1..2 " "
+    Plus@2..3 "+"
+    Whitespace@3..4 " "
+    ErrorNode@4..8
+      Error@4..5 "@"
+      Whitespace@5..6 " "
+      Error@6..7 "$"
+      Whitespace@7..8 " "
+    Number@8..9 "2"
+"#,
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(4.into(), 7.into())]);
+    }
+
+    #[test]
+    fn junk_in_operator_position_is_shown_as_written() {
+        let parse = Parser::new("1 abc + 2").parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(2.into(), 5.into())]);
+        assert!(parse
+            .errors()
+            .next()
+            .unwrap()
+            .starts_with("found `abc`, expected a plus sign"));
+    }
+
+    #[test]
+    fn missing_operand_after_junk_is_not_reported_again() {
+        let parse = Parser::new("1 + @@").parse();
+
+        assert_eq!(parse.errors().len(), 1);
+        assert!(parse.format().contains("Missing@6..6"));
+    }
+
+    #[test]
+    fn comments_are_kept_in_the_tree() {
+        let parse = Parser::new("1 + /* two */ 2 // done").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..23
+  Operation@0..23
+    Number@0..1 "1"
+    Whitespace@1..2 " "
+    Plus@2..3 "+"
+    Whitespace@3..4 " "
+    BlockComment@4..13 "/* two */"
+    Whitespace@13..14 " "
+    Number@14..15 "2"
+    Whitespace@15..16 " "
+    LineComment@16..23 "// done"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn comments_between_statements_belong_to_the_root() {
+        let parse = Parser::new("let x = 1; // one\n/* /* nested */ */ x").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..38
+  LetStmt@0..10
+    LetKw@0..3 "let"
+    Whitespace@3..4 " "
+    Ident@4..5 "x"
+    Whitespace@5..6 " "
+    Equals@6..7 "="
+    Whitespace@7..8 " "
+    Number@8..9 "1"
+    Semicolon@9..10 ";"
+  Whitespace@10..11 " "
+  LineComment@11..17 "// one"
+  Whitespace@17..18 "\n"
+  BlockComment@18..36 "/* /* nested */ */"
+  Whitespace@36..37 " "
+  NameRef@37..38
+    Ident@37..38 "x"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn unterminated_block_comment_is_reported_at_opening_delimiter() {
+        let parse = Parser::new("1 /* a /* b */").parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(2.into(), 4.into())]);
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["unterminated block comment"],
+        );
+    }
+
+    #[test]
+    fn parse_decimal_literals() {
+        let parse = Parser::new("6.02e-23*.5").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..11
+  Operation@0..11
+    Number@0..8 "6.02e-23"
+    Star@8..9 "*"
+    Number@9..11 ".5"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn parse_string_literal() {
+        let parse = Parser::new(r#""a" + x"#).parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..7
+  Operation@0..7
+    String@0..3 "\"a\""
+    Whitespace@3..4 " "
+    Plus@4..5 "+"
+    Whitespace@5..6 " "
+    NameRef@6..7
+      Ident@6..7 "x"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn malformed_escapes_are_reported_at_their_range() {
+        let parse = Parser::new(r#""a\qb" + "\u{41""#).parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(
+            errors,
+            [
+                TextRange::new(2.into(), 4.into()),
+                TextRange::new(10.into(), 15.into()),
+            ],
+        );
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            [
+                "unknown escape sequence",
+                "unicode escapes must be `\\u{...}` with 1 to 6 hex digits",
+            ],
+        );
+    }
+
+    #[test]
+    fn unterminated_string_is_reported_at_opening_quote() {
+        let parse = Parser::new(r#"1 + "abc"#).parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(4.into(), 5.into())]);
+        assert_eq!(parse.errors().collect::<Vec<_>>(), ["unterminated string"]);
+    }
+
+    #[test]
+    fn decimal_point_without_digits_is_reported() {
+        let parse = Parser::new("1 + 12.").parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(6.into(), 7.into())]);
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["expected digits after the decimal point"],
+        );
+    }
+
+    #[test]
+    fn exponent_without_digits_is_reported() {
+        let parse = Parser::new("2.5e-").parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(3.into(), 5.into())]);
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["expected digits in the exponent"],
+        );
+    }
+
+    #[test]
+    fn invalid_digit_for_radix_is_reported() {
+        let parse = Parser::new("0xff + 0b102").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..12
+  Operation@0..12
+    Number@0..4 "0xff"
+    Whitespace@4..5 " "
+    Plus@5..6 "+"
+    Whitespace@6..7 " "
+    Number@7..12 "0b102"
+"#,
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(11.into(), 12.into())]);
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["invalid digit for a binary literal"],
+        );
+    }
+
+    #[test]
+    fn parse_variable_references() {
+        let parse = Parser::new("price * qty").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..11
+  Operation@0..11
+    NameRef@0..5
+      Ident@0..5 "price"
+    Whitespace@5..6 " "
+    Star@6..7 "*"
+    Whitespace@7..8 " "
+    NameRef@8..11
+      Ident@8..11 "qty"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_function_call() {
+        let parse = Parser::new("max(1, x*2)").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..11
+  CallExpr@0..11
+    NameRef@0..3
+      Ident@0..3 "max"
+    ArgList@3..11
+      LParen@3..4 "("
+      Number@4..5 "1"
+      Comma@5..6 ","
+      Whitespace@6..7 " "
+      Operation@7..10
+        NameRef@7..8
+          Ident@7..8 "x"
+        Star@8..9 "*"
+        Number@9..10 "2"
+      RParen@10..11 ")"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn parse_function_call_without_arguments() {
+        let parse = Parser::new("f()").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..3
+  CallExpr@0..3
+    NameRef@0..1
+      Ident@0..1 "f"
+    ArgList@1..3
+      LParen@1..2 "("
+      RParen@2..3 ")"
+"#,
+        );
+    }
+
+    #[test]
+    fn calls_bind_tighter_than_prefix_operators() {
+        let parse = Parser::new("-f(1)").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..5
+  PrefixExpr@0..5
+    Minus@0..1 "-"
+    CallExpr@1..5
+      NameRef@1..2
+        Ident@1..2 "f"
+      ArgList@2..5
+        LParen@2..3 "("
+        Number@3..4 "1"
+        RParen@4..5 ")"
+"#,
+        );
+    }
+
+    #[test]
+    fn unclosed_argument_list_is_reported_at_opening_paren() {
+        let parse = Parser::new("f(1, 2").parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(1.into(), 2.into())]);
+    }
+
+    #[test]
+    fn parse_list_literal() {
+        let parse = Parser::new("[1, x]").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..6
+  ListExpr@0..6
+    LBracket@0..1 "["
+    Number@1..2 "1"
+    Comma@2..3 ","
+    Whitespace@3..4 " "
+    NameRef@4..5
+      Ident@4..5 "x"
+    RBracket@5..6 "]"
+"#,
+        );
+        assert_eq!(parse.errors().len(), 0);
+    }
+
+    #[test]
+    fn indexing_chains_to_the_left() {
+        let parse = Parser::new("xs[0][1]").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..8
+  IndexExpr@0..8
+    IndexExpr@0..5
+      NameRef@0..2
+        Ident@0..2 "xs"
+      LBracket@2..3 "["
+      Number@3..4 "0"
+      RBracket@4..5 "]"
+    LBracket@5..6 "["
+    Number@6..7 "1"
+    RBracket@7..8 "]"
+"#,
+        );
+    }
+
+    #[test]
+    fn indexing_binds_tighter_than_prefix_operators() {
+        let parse = Parser::new("-xs[0]").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..6
+  PrefixExpr@0..6
+    Minus@0..1 "-"
+    IndexExpr@1..6
+      NameRef@1..3
+        Ident@1..3 "xs"
+      LBracket@3..4 "["
+      Number@4..5 "0"
+      RBracket@5..6 "]"
+"#,
+        );
+    }
+
+    #[test]
+    fn unclosed_bracket_is_reported_at_opening_bracket() {
+        for &(input, opening) in [("[1, 2", 0_u32), ("xs[0", 2)].iter() {
+            let parse = Parser::new(input).parse();
+
+            let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+            assert_eq!(
+                errors,
+                [TextRange::new(opening.into(), (opening + 1).into())]
+            );
+            assert_eq!(parse.errors().collect::<Vec<_>>(), ["unclosed bracket"]);
+        }
+    }
+
+    #[test]
+    fn parse_negation() {
+        let parse = Parser::new("-5").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..2
+  PrefixExpr@0..2
+    Minus@0..1 "-"
+    Number@1..2 "5"
+"#,
+        );
+    }
+
+    #[test]
+    fn negation_has_higher_precedence_than_multiplication() {
+        let parse = Parser::new("-2*3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..4
+  Operation@0..4
+    PrefixExpr@0..2
+      Minus@0..1 "-"
+      Number@1..2 "2"
+    Star@2..3 "*"
+    Number@3..4 "3"
+"#,
+        );
+    }
+
+    #[test]
+    fn prefix_operators_can_follow_infix_operators() {
+        let parse = Parser::new("2 * -+3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..7
+  Operation@0..7
+    Number@0..1 "2"
+    Whitespace@1..2 " "
+    Star@2..3 "*"
+    Whitespace@3..4 " "
+    PrefixExpr@4..7
+      Minus@4..5 "-"
+      PrefixExpr@5..7
+        Plus@5..6 "+"
+        Number@6..7 "3"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_parenthesized_number() {
+        let parse = Parser::new("(1)").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..3
+  ParenExpr@0..3
+    LParen@0..1 "("
+    Number@1..2 "1"
+    RParen@2..3 ")"
+"#,
+        );
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        let parse = Parser::new("(1+2)*3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..7
+  Operation@0..7
+    ParenExpr@0..5
+      LParen@0..1 "("
+      Operation@1..4
+        Number@1..2 "1"
+        Plus@2..3 "+"
+        Number@3..4 "2"
+      RParen@4..5 ")"
+    Star@5..6 "*"
+    Number@6..7 "3"
+"#,
+        );
+    }
+
+    #[test]
+    fn parse_nested_parentheses() {
+        let parse = Parser::new("( (1) )").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..7
+  ParenExpr@0..7
+    LParen@0..1 "("
+    Whitespace@1..2 " "
+    ParenExpr@2..5
+      LParen@2..3 "("
+      Number@3..4 "1"
+      RParen@4..5 ")"
+    Whitespace@5..6 " "
+    RParen@6..7 ")"
+"#,
+        );
+    }
+
+    #[test]
+    fn deeply_nested_parens_are_reported_once() {
+        let depth = 20_000;
+        let input = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        let parse = Parser::new(&input).parse();
+
+        let errors: Vec<_> = parse
+            .syntax_errors()
+            .iter()
+            .map(|error| error.kind())
+            .collect();
+        assert_eq!(errors, [&SyntaxErrorKind::NestedTooDeeply]);
+        assert_eq!(parse.errors[0].range.start(), TextSize::from(256));
+        assert!(parse.eval().is_err());
+    }
+
+    #[test]
+    fn unclosed_deeply_nested_parens_do_not_overflow() {
+        let parse = Parser::new(&"(".repeat(20_000)).parse();
+
+        assert!(parse
+            .syntax_errors()
+            .iter()
+            .any(|error| error.kind() == &SyntaxErrorKind::NestedTooDeeply));
+        assert!(parse.eval().is_err());
+    }
+
+    #[test]
+    fn long_runs_of_prefix_operators_are_reported_once() {
+        let parse = Parser::new(&format!("{}1", "-".repeat(100_000))).parse();
+
+        let errors: Vec<_> = parse
+            .syntax_errors()
+            .iter()
+            .map(|error| error.kind())
+            .collect();
+        assert_eq!(errors, [&SyntaxErrorKind::NestedTooDeeply]);
+        assert_eq!(
+            parse.syntax().text_range(),
+            TextRange::up_to(TextSize::from(100_001))
+        );
+        assert!(parse.eval().is_err());
+    }
+
+    #[test]
+    fn long_chains_of_operators_are_not_nested_too_deeply() {
+        let input = format!("1{}", "+1".repeat(1_000));
+        let parse = Parser::new(&input).parse();
+
+        assert_eq!(parse.errors().len(), 0);
+        assert_eq!(parse.eval(), Ok(Value::Number(1_001)));
+    }
+
+    #[test]
+    fn long_chains_of_calls_are_reported_once() {
+        let input = format!("f{}", "()".repeat(50_000));
+        let parse = Parser::new(&input).parse();
+
+        let errors: Vec<_> = parse
+            .syntax_errors()
+            .iter()
+            .map(|error| error.kind())
+            .collect();
+        assert_eq!(errors, [&SyntaxErrorKind::NestedTooDeeply]);
+    }
+
+    #[test]
+    fn nesting_up_to_the_limit_is_evaluated() {
+        let depth = MAX_DEPTH - 1;
+        let input = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        let parse = Parser::new(&input).parse();
+
+        assert_eq!(parse.errors().len(), 0);
+        assert_eq!(parse.eval(), Ok(Value::Number(1)));
+    }
+
+    #[test]
+    fn unclosed_paren_is_reported_at_opening_paren() {
+        let parse = Parser::new("2*(1+2").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..6
+  Operation@0..6
+    Number@0..1 "2"
+    Star@1..2 "*"
+    ParenExpr@2..6
+      LParen@2..3 "("
+      Operation@3..6
+        Number@3..4 "1"
+        Plus@4..5 "+"
+        Number@5..6 "2"
+"#,
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(2.into(), 3.into())]);
+        assert_eq!(parse.errors().collect::<Vec<_>>(), ["unclosed parenthesis"]);
+    }
+
+    #[test]
+    fn trailing_input_is_kept_and_reported() {
+        let parse = Parser::new("1 + 2) * 3").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..10
+  Operation@0..5
+    Number@0..1 "1"
+    Whitespace@1..2 " "
+    Plus@2..3 "+"
+    Whitespace@3..4 " "
+    Number@4..5 "2"
+  ErrorNode@5..10
+    RParen@5..6 ")"
+    Whitespace@6..7 " "
+    Star@7..8 "*"
+    Whitespace@8..9 " "
+    Number@9..10 "3"
+"#,
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(5.into(), 10.into())]);
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["unexpected trailing input"],
+        );
+    }
+
+    #[test]
+    fn let_after_expression_starts_a_new_statement() {
+        let parse = Parser::new("1 + 2 let y = 1; y").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..18
+  ExprStmt@0..6
+    Operation@0..6
+      Number@0..1 "1"
+      Whitespace@1..2 " "
+      Plus@2..3 "+"
+      Whitespace@3..4 " "
+      Number@4..5 "2"
+      Whitespace@5..6 " "
+  LetStmt@6..16
+    LetKw@6..9 "let"
+    Whitespace@9..10 " "
+    Ident@10..11 "y"
+    Whitespace@11..12 " "
+    Equals@12..13 "="
+    Whitespace@13..14 " "
+    Number@14..15 "1"
+    Semicolon@15..16 ";"
+  Whitespace@16..17 " "
+  NameRef@17..18
+    Ident@17..18 "y"
+"#,
+        );
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(6.into(), 9.into())]);
+        assert_eq!(
+            parse.errors().collect::<Vec<_>>(),
+            ["found `let`, expected a semicolon"]
+        );
+        assert_eq!(
+            parse.errors[0].suggestions()[0].range,
+            TextRange::empty(5.into())
+        );
+    }
+
+    #[test]
+    fn let_in_place_of_operand_is_reported_once() {
+        let parse = Parser::new("1 && let").parse();
+
+        assert_eq!(
+            parse.format(),
+            r#"Root@0..8
+  ExprStmt@0..5
+    Operation@0..5
+      Number@0..1 "1"
+      Whitespace@1..2 " "
+      AmpAmp@2..4 "&&"
+      Whitespace@4..5 " "
+      Missing@5..5
+  LetStmt@5..8
+    LetKw@5..8 "let"
+    Missing@8..8
+  Missing@8..8
+"#,
+        );
+
+        // Only the first error is about the `let` itself. The rest are about the empty let
+        // statement, and are the same as for `let` on its own.
+        let errors: Vec<_> = parse.errors.iter().map(|error| &error.kind).collect();
+        assert_eq!(
+            errors,
+            [
+                &SyntaxErrorKind::FoundExpected {
+                    found: SyntaxKind::LetKw,
+                    expected: EXPR_FIRST,
+                },
+                &SyntaxErrorKind::Expected {
+                    expected: &[SyntaxKind::Ident],
+                },
+                &SyntaxErrorKind::Expected {
+                    expected: &[SyntaxKind::Equals],
+                },
+                &SyntaxErrorKind::Expected {
+                    expected: EXPR_FIRST,
+                },
+                &SyntaxErrorKind::Expected {
+                    expected: &[SyntaxKind::Semicolon],
+                },
+                &SyntaxErrorKind::Expected {
+                    expected: EXPR_FIRST,
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn trailing_trivia_is_not_part_of_trailing_input() {
+        let parse = Parser::new("1 then 2 // two\n").parse();
+
+        let errors: Vec<_> = parse.errors.iter().map(|error| error.range).collect();
+        assert_eq!(errors, [TextRange::new(2.into(), 8.into())]);
+    }
+
+    #[test]
+    fn trailing_input_with_nothing_before_it_is_reported_once() {
+        let parse = Parser::new(")").parse();
+
+        assert_eq!(parse.errors().len(), 1);
+        assert_eq!(
+            parse.syntax().text_range(),
+            TextRange::new(0.into(), 1.into())
+        );
+    }
+
+    // Applies the first suggestion of every error, which should leave input that parses cleanly.
+    fn apply_suggestions(input: &str) -> String {
+        let parse = Parser::new(input).parse();
+        let mut suggestions: Vec<_> = parse
+            .syntax_errors()
+            .iter()
+            .filter_map(|error| error.suggestions().first())
+            .collect();
+
+        // Apply edits from the end so that earlier ranges stay valid.
+        suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.range.start()));
+
+        let mut fixed = input.to_string();
+        for suggestion in suggestions {
+            let range: std::ops::Range<usize> = suggestion.range.into();
+            fixed.replace_range(range, &suggestion.replacement);
+        }
+
+        fixed
+    }
+
+    #[test]
+    fn unclosed_paren_suggests_closing_before_trailing_comment() {
+        let parse = Parser::new("(1 + 2 // sum").parse();
+
+        assert_eq!(
+            parse.syntax_errors()[0].suggestions(),
+            [Suggestion {
+                range: TextRange::empty(6.into()),
+                replacement: ")".to_string(),
+            }],
+        );
+        assert_eq!(apply_suggestions("(1 + 2 // sum"), "(1 + 2) // sum");
+    }
+
+    #[test]
+    fn unclosed_delimiters_suggest_closing() {
+        assert_eq!(apply_suggestions("[1, 2"), "[1, 2]");
+        assert_eq!(apply_suggestions("xs[0"), "xs[0]");
+        assert_eq!(apply_suggestions("fn(x,"), "fn(x,)");
+    }
+
+    #[test]
+    fn junk_suggests_removal() {
+        assert_eq!(apply_suggestions("1 + @ 2"), "1 +  2");
+    }
+
+    #[test]
+    fn trailing_input_has_no_suggestion() {
+        let parse = Parser::new("1 + 2) * 3").parse();
+
+        assert_eq!(
+            parse.syntax_errors()[0].kind(),
+            &SyntaxErrorKind::TrailingInput,
+        );
+        assert_eq!(parse.syntax_errors()[0].suggestions(), []);
+    }
+
+    #[test]
+    fn missing_semicolon_after_let_is_suggested() {
+        assert_eq!(apply_suggestions("let x = 1 // one"), "let x = 1; // one");
+    }
+
+    #[test]
+    fn malformed_literals_suggest_fixes() {
+        assert_eq!(apply_suggestions("1."), "1.0");
+        assert_eq!(apply_suggestions(r#""abc"#), r#""abc""#);
+    }
+
+    #[test]
+    fn invalid_escape_has_no_suggestion() {
+        let parse = Parser::new(r#""a\qb""#).parse();
+
+        assert_eq!(parse.syntax_errors()[0].suggestions(), []);
+        assert_eq!(
+            parse.syntax_errors()[0].kind().help().as_deref(),
+            Some(r"to include a backslash itself, escape it as `\\`"),
+        );
+    }
+
+    #[test]
+    fn unterminated_string_is_not_closed_if_that_would_not_fix_it() {
+        let parse = Parser::new(r#""\u{"#).parse();
+
+        assert!(parse
+            .syntax_errors()
+            .iter()
+            .all(|error| error.suggestions().is_empty()));
+    }
+
+    #[test]
+    fn chained_comparison_has_no_suggestion() {
+        // Any operator swap would change what the expression means, so the fix is left to help
+        // text instead.
+        let parse = Parser::new("1 < 2 < 3").parse();
+
+        assert_eq!(parse.syntax_errors().len(), 1);
+        assert_eq!(parse.syntax_errors()[0].suggestions(), []);
+    }
+
+    #[test]
+    fn diagnostics_carry_codes() {
+        let parse = Parser::new("(1").parse();
+        let diagnostic = parse.diagnostics(()).next().unwrap();
+
+        assert_eq!(diagnostic.code.as_deref(), Some("E0003"));
+        assert_eq!(diagnostic.labels[1].message, "help: insert `)`");
+    }
+
+    // Fragments that are likely to trip the parser up when strung together at random.
+    const FRAGMENTS: &[&str] = &[
+        "1", "2.5", "0x", "x", "f", "\"s\"", "\"", "\\", "true", "if", "then", "else", "let", "fn",
+        "+", "-", "*", "/", "^", "==", "<", "&&", "!", "=", ";", ",", "(", ")", "[", "]", "//",
+        "/*", "*/", " ", "\n", "\r\n", "\t", "\u{A0}", "@", "é",
+    ];
+
+    proptest! {
+        #[test]
+        fn tree_covers_any_input(input in "\\PC*") {
+            let parse = Parser::new(&input).parse();
+
+            prop_assert_eq!(
+                parse.syntax().text_range(),
+                TextRange::up_to(TextSize::try_from(input.len()).unwrap()),
+            );
+            prop_assert_eq!(parse.syntax().text().to_string(), input);
+        }
+
+        #[test]
+        fn tree_covers_any_sequence_of_fragments(
+            fragments in prop::collection::vec(prop::sample::select(FRAGMENTS), 0..40),
+        ) {
+            let input = fragments.concat();
+            let parse = Parser::new(&input).parse();
+
+            prop_assert_eq!(
+                parse.syntax().text_range(),
+                TextRange::up_to(TextSize::try_from(input.len()).unwrap()),
+            );
+            prop_assert_eq!(parse.syntax().text().to_string(), input);
+        }
     }
 }
@@ -0,0 +1,141 @@
+use crate::ast::Lambda;
+use crate::env::Locals;
+use smol_str::SmolStr;
+use std::fmt;
+
+// The result of evaluating an expression. Numbers are stored using whichever Numeric type the
+// expression is being evaluated with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<T = i64> {
+    Number(T),
+    Bool(bool),
+    String(String),
+    List(Vec<Value<T>>),
+    Function(Closure<T>),
+}
+
+impl<T> Value<T> {
+    pub fn ty(&self) -> ValueType {
+        match self {
+            Self::Number(_) => ValueType::Number,
+            Self::Bool(_) => ValueType::Bool,
+            Self::String(_) => ValueType::String,
+            Self::List(_) => ValueType::List,
+            Self::Function(_) => ValueType::Function,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&T> {
+        match self {
+            Self::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl<T> From<T> for Value<T> {
+    fn from(n: T) -> Self {
+        Self::Number(n)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Value<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => n.fmt(f),
+            Self::Bool(b) => b.fmt(f),
+            // Strings are shown the way they would be written in an expression, which happens to
+            // use the same escapes as Rust.
+            Self::String(s) => write!(f, "{:?}", s),
+            Self::List(items) => {
+                f.write_str("[")?;
+
+                for (idx, item) in items.iter().enumerate() {
+                    if idx != 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    item.fmt(f)?;
+                }
+
+                f.write_str("]")
+            }
+            Self::Function(closure) => {
+                f.write_str(closure.lambda.syntax().text().to_string().trim_end())
+            }
+        }
+    }
+}
+
+// A function created by a lambda expression, together with the local variables that were in scope
+// where it was created.
+#[derive(Clone)]
+pub struct Closure<T> {
+    pub(crate) lambda: Lambda,
+    pub(crate) captured: Locals<T>,
+    // The name the closure was bound to with let, which it can use to call itself.
+    pub(crate) name: Option<SmolStr>,
+}
+
+impl<T> Closure<T> {
+    pub fn lambda(&self) -> &Lambda {
+        &self.lambda
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+// Closures are equal only if they come from the same lambda and captured the very same variables.
+impl<T> PartialEq for Closure<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.lambda == other.lambda
+            && self.name == other.name
+            && self.captured.ptr_eq(&other.captured)
+    }
+}
+
+impl<T> fmt::Debug for Closure<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("lambda", &self.lambda)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValueType {
+    Number,
+    Bool,
+    String,
+    List,
+    Function,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Number => "a number",
+            Self::Bool => "a boolean",
+            Self::String => "a string",
+            Self::List => "a list",
+            Self::Function => "a function",
+        })
+    }
+}